@@ -3,6 +3,9 @@
 use super::table::TABLE;
 use std::{mem, slice};
 
+/// # Safety
+/// The implementor must be a plain integer or an array of integers,
+/// which means any bit pattern of it is valid.
 pub unsafe trait BitStorage: Sized {
     fn bit_size() -> usize {
         mem::size_of::<Self>() * 8
@@ -37,13 +40,8 @@ impl<S: BitStorage> FixedBitSet<S> {
     }
 
     pub fn one() -> Self {
-        let mut set = Self {
-            buf: unsafe { mem::MaybeUninit::uninit().assume_init() },
-        };
-        set.buf
-            .as_bytes_mut()
-            .iter_mut()
-            .for_each(|x| *x = u8::max_value());
+        let mut set = Self::zero();
+        set.buf.as_bytes_mut().iter_mut().for_each(|x| *x = u8::MAX);
         set
     }
 }
@@ -65,10 +63,18 @@ impl<S: BitStorage> FixedBitSet<S> {
             .for_each(|(lhs, rhs)| *lhs |= rhs)
     }
 
+    pub fn difference_with(&mut self, other: &Self) {
+        self.buf
+            .as_bytes_mut()
+            .iter_mut()
+            .zip(other.buf.as_bytes().iter())
+            .for_each(|(lhs, rhs)| *lhs &= !rhs)
+    }
+
     pub fn set(&mut self, index: usize, bit: bool) {
         let idx = index / 8;
         let offset: u8 = (index % 8) as _;
-        let mask = (bit as u8) << offset;
+        let mask = 1_u8 << offset;
         let bytes = self.buf.as_bytes_mut();
         let pos: &mut u8 = match bytes.get_mut(idx) {
             Some(pos) => pos,
@@ -78,7 +84,15 @@ impl<S: BitStorage> FixedBitSet<S> {
                 S::bit_size()
             ),
         };
-        *pos |= mask
+        if bit {
+            *pos |= mask
+        } else {
+            *pos &= !mask
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.buf.as_bytes().iter().all(|&x| x == 0)
     }
 
     pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
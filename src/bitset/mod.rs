@@ -1,4 +1,5 @@
 mod fixed_bitset;
+mod small_bitset;
 mod table;
 
-pub use self::fixed_bitset::{BitStorage, FixedBitSet};
+pub use self::small_bitset::SmallBitSet;
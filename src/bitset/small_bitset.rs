@@ -0,0 +1,89 @@
+use super::fixed_bitset::{BitStorage, FixedBitSet};
+
+use smallvec::SmallVec;
+
+/// A growable bitset made of `FixedBitSet` blocks.
+///
+/// The first block is stored inline, so small tables never touch the heap.
+/// Missing blocks are treated as zeros.
+#[derive(Debug, Clone)]
+pub struct SmallBitSet<S: BitStorage + Clone> {
+    blocks: SmallVec<[FixedBitSet<S>; 1]>,
+}
+
+impl<S: BitStorage + Clone> SmallBitSet<S> {
+    pub fn zero() -> Self {
+        Self {
+            blocks: SmallVec::new(),
+        }
+    }
+
+    /// Returns a bitset whose first `len` bits (rounded up to a whole block) are ones.
+    pub fn one(len: usize) -> Self {
+        let num = len.div_ceil(S::bit_size());
+        let mut blocks = SmallVec::with_capacity(num);
+        blocks.resize_with(num, FixedBitSet::one);
+        Self { blocks }
+    }
+}
+
+impl<S: BitStorage + Clone> SmallBitSet<S> {
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.blocks.truncate(other.blocks.len());
+        self.blocks
+            .iter_mut()
+            .zip(other.blocks.iter())
+            .for_each(|(lhs, rhs)| lhs.intersect_with(rhs))
+    }
+
+    pub fn union_with(&mut self, other: &Self) {
+        if self.blocks.len() < other.blocks.len() {
+            self.blocks
+                .resize_with(other.blocks.len(), FixedBitSet::zero);
+        }
+        self.blocks
+            .iter_mut()
+            .zip(other.blocks.iter())
+            .for_each(|(lhs, rhs)| lhs.union_with(rhs))
+    }
+
+    pub fn difference_with(&mut self, other: &Self) {
+        self.blocks
+            .iter_mut()
+            .zip(other.blocks.iter())
+            .for_each(|(lhs, rhs)| lhs.difference_with(rhs))
+    }
+
+    pub fn set(&mut self, index: usize, bit: bool) {
+        let idx = index / S::bit_size();
+        let offset = index % S::bit_size();
+        if idx >= self.blocks.len() {
+            if !bit {
+                return;
+            }
+            self.blocks.resize_with(idx + 1, FixedBitSet::zero);
+        }
+        self.blocks[idx].set(offset, bit);
+    }
+
+    /// Removes the bit at `index` and shifts all higher bits down by one.
+    pub fn remove(&mut self, index: usize) {
+        let ones: SmallVec<[usize; 16]> = self.iter_ones().filter(|&i| i != index).collect();
+        self.blocks.clear();
+        for i in ones {
+            self.set(if i > index { i - 1 } else { i }, true);
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.blocks.iter().all(FixedBitSet::is_zero)
+    }
+
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        let size = S::bit_size();
+        self.blocks
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, b)| b.iter_ones().map(move |j| i * size + j))
+    }
+}
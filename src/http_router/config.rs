@@ -0,0 +1,138 @@
+use super::{HttpRouter, Method};
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// A declarative route table which can be loaded from and dumped to any serde format.
+///
+/// ```toml
+/// [[routes]]
+/// method = "GET"
+/// pattern = "/user/:id"
+/// handler = "get_user"
+/// name = "user"
+///
+/// [[nested]]
+/// prefix = "/api"
+///
+/// [[nested.routes]]
+/// method = "POST"
+/// pattern = "/login"
+/// handler = "login"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouterConfig {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub routes: Vec<RouteConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nested: Vec<NestedConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RouteConfig {
+    pub method: String,
+    pub pattern: String,
+    /// The key resolved to the data of the route.
+    pub handler: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NestedConfig {
+    pub prefix: String,
+    #[serde(flatten)]
+    pub router: RouterConfig,
+}
+
+/// An error occurred when building a router from a [`RouterConfig`].
+#[derive(Debug)]
+pub struct ConfigError {
+    entry: String,
+    msg: &'static str,
+}
+
+impl ConfigError {
+    fn new(entry: String, msg: &'static str) -> Self {
+        Self { entry, msg }
+    }
+
+    /// Returns the path of the offending entry, such as `nested[0].routes[1]`.
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.entry, self.msg)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl<T> HttpRouter<T> {
+    /// Builds a router from `config`, resolving each handler key with `resolve`.
+    pub fn from_config(
+        config: &RouterConfig,
+        mut resolve: impl FnMut(&str) -> Option<T>,
+    ) -> Result<Self, ConfigError> {
+        let mut router = Self::new();
+        router.load_config(config, "", &mut resolve)?;
+        Ok(router)
+    }
+
+    /// Dumps the routes to a flat config, naming the data of each route with `handler_key`.
+    ///
+    /// Nested routes are listed with their full patterns.
+    pub fn to_config(&self, mut handler_key: impl FnMut(&T) -> String) -> RouterConfig {
+        let routes = self
+            .route_entries()
+            .into_iter()
+            .map(|(method, pattern, name, data)| RouteConfig {
+                method: method.as_str().to_owned(),
+                pattern,
+                handler: handler_key(data),
+                name: name.map(Into::into),
+            })
+            .collect();
+        RouterConfig {
+            routes,
+            nested: Vec::new(),
+        }
+    }
+
+    fn load_config(
+        &mut self,
+        config: &RouterConfig,
+        path: &str,
+        resolve: &mut dyn FnMut(&str) -> Option<T>,
+    ) -> Result<(), ConfigError> {
+        for (i, route) in config.routes.iter().enumerate() {
+            let entry = || format!("{}routes[{}]", path, i);
+
+            let method = Method::from_bytes(route.method.as_bytes())
+                .map_err(|_| ConfigError::new(entry(), "invalid method"))?;
+            let data = resolve(&route.handler)
+                .ok_or_else(|| ConfigError::new(entry(), "unknown handler key"))?;
+
+            let ans = match route.name {
+                Some(ref name) => self.try_insert_named(method, name, &route.pattern, data),
+                None => self.try_insert(method, &route.pattern, data),
+            };
+            ans.map_err(|e| ConfigError::new(entry(), e.msg()))?;
+        }
+
+        for (i, nested) in config.nested.iter().enumerate() {
+            let entry = format!("{}nested[{}]", path, i);
+            let mut ans = Ok(());
+            let nest = self.try_nest(&nested.prefix, |r| {
+                ans = r.load_config(&nested.router, &format!("{}.", entry), resolve)
+            });
+            ans?;
+            nest.map_err(|e| ConfigError::new(entry, e.msg()))?;
+        }
+        Ok(())
+    }
+}
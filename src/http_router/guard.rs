@@ -0,0 +1,122 @@
+use http::header::{HeaderName, ACCEPT, CONTENT_TYPE};
+use http::request::Parts;
+use http::StatusCode;
+
+use std::fmt;
+use std::sync::Arc;
+
+/// A predicate over the parts of a request, checked after the path of a route is matched.
+///
+/// A route is skipped if any of its guards fails. When all routes of a path are skipped,
+/// a failed [`Guard::accept`] responds `406 Not Acceptable`
+/// and a failed [`Guard::content_type`] responds `415 Unsupported Media Type`.
+#[derive(Clone)]
+pub struct Guard {
+    rejection: Option<StatusCode>,
+    predicate: Arc<dyn Fn(&Parts) -> bool + Send + Sync>,
+}
+
+impl Guard {
+    pub fn new(predicate: impl Fn(&Parts) -> bool + Send + Sync + 'static) -> Self {
+        Self {
+            rejection: None,
+            predicate: Arc::new(predicate),
+        }
+    }
+
+    /// Requires a header with the value `value`.
+    pub fn header(name: HeaderName, value: &str) -> Self {
+        let value: Box<str> = value.into();
+        Self::new(move |parts| {
+            parts
+                .headers
+                .get_all(&name)
+                .iter()
+                .any(|v| v.as_bytes() == value.as_bytes())
+        })
+    }
+
+    /// Requires a query parameter with the value `value`, or only its presence if `value` is `None`.
+    ///
+    /// The query is compared without percent-decoding.
+    pub fn query(name: &str, value: Option<&str>) -> Self {
+        let name: Box<str> = name.into();
+        let value: Option<Box<str>> = value.map(Into::into);
+        Self::new(move |parts| {
+            let query = parts.uri.query().unwrap_or("");
+            query.split('&').any(|pair| {
+                let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                k == &*name && (value.is_none() || value.as_deref() == Some(v))
+            })
+        })
+    }
+
+    /// Requires the `Accept` header to accept `mime`, such as `application/json`.
+    ///
+    /// A request without the header accepts any type.
+    pub fn accept(mime: &str) -> Self {
+        let mime: Box<str> = mime.into();
+        let mut guard = Self::new(move |parts| {
+            let mut values = parts.headers.get_all(ACCEPT).iter().peekable();
+            if values.peek().is_none() {
+                return true;
+            }
+            values.any(|v| matches!(v.to_str(), Ok(v) if accepts(v, &mime)))
+        });
+        guard.rejection = Some(StatusCode::NOT_ACCEPTABLE);
+        guard
+    }
+
+    /// Requires the media type of `Content-Type` to be `mime`, ignoring the parameters.
+    pub fn content_type(mime: &str) -> Self {
+        let mime: Box<str> = mime.into();
+        let mut guard = Self::new(move |parts| match parts.headers.get(CONTENT_TYPE) {
+            Some(v) => matches!(v.to_str(), Ok(v) if essence(v).eq_ignore_ascii_case(&mime)),
+            None => false,
+        });
+        guard.rejection = Some(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        guard
+    }
+
+    pub fn check(&self, parts: &Parts) -> bool {
+        (self.predicate)(parts)
+    }
+
+    /// Returns the status of the response when the guard fails for all routes of a path.
+    pub fn rejection(&self) -> Option<StatusCode> {
+        self.rejection
+    }
+}
+
+impl fmt::Debug for Guard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Guard")
+            .field("rejection", &self.rejection)
+            .finish()
+    }
+}
+
+fn essence(media: &str) -> &str {
+    media.split(';').next().unwrap_or("").trim()
+}
+
+/// Checks whether the media ranges of an `Accept` header accept `mime`.
+fn accepts(header: &str, mime: &str) -> bool {
+    let (ty, subtype) = mime.split_once('/').unwrap_or((mime, ""));
+    header.split(',').any(|range| {
+        let mut params = range.split(';').skip(1);
+        let refused = params.any(|p| match p.trim().strip_prefix("q=") {
+            Some(q) => q.trim().parse::<f32>() == Ok(0.0),
+            None => false,
+        });
+        if refused {
+            return false;
+        }
+        match essence(range).split_once('/') {
+            Some(("*", "*")) => true,
+            Some((t, "*")) => t.eq_ignore_ascii_case(ty),
+            Some((t, s)) => t.eq_ignore_ascii_case(ty) && s.eq_ignore_ascii_case(subtype),
+            None => false,
+        }
+    })
+}
@@ -0,0 +1,247 @@
+use super::{HttpRouter, Method};
+use crate::router::{is_name_char, Captures, RouterError};
+
+use std::collections::HashMap;
+
+use smallvec::SmallVec;
+
+/// Selects a [`HttpRouter`] by the host of a request.
+///
+/// A host pattern is an exact host such as `api.example.com`, a wildcard such as `*.example.com`
+/// which matches one or more leading labels, or a pattern with captures such as `:tenant.example.com`.
+/// Exact hosts take precedence over patterns with captures, which take precedence over wildcards.
+///
+/// Hosts are matched case-insensitively, ignoring the port and a trailing dot.
+#[derive(Debug, Default)]
+pub struct HostRouter<T> {
+    exact: HashMap<Box<str>, HttpRouter<T>>,
+    patterns: Vec<(HostPattern, HttpRouter<T>)>,
+    default: Option<HttpRouter<T>>,
+}
+
+#[derive(Debug, PartialEq)]
+struct HostPattern {
+    wildcard: bool,
+    labels: Vec<Label>,
+}
+
+#[derive(Debug)]
+enum Label {
+    Static(Box<str>),
+    Capture(Box<str>),
+}
+
+/// The captures of a host pattern.
+pub(crate) type HostCaptures<'s, 'h> = SmallVec<[(&'s str, &'h str); 4]>;
+
+impl<T> HostRouter<T> {
+    pub fn new() -> Self {
+        Self {
+            exact: HashMap::new(),
+            patterns: Vec::new(),
+            default: None,
+        }
+    }
+
+    pub fn insert(&mut self, host: &str, router: HttpRouter<T>) -> &mut Self {
+        if let Err(e) = self.try_insert(host, router) {
+            panic!("{}: host = {:?}", e, host);
+        }
+        self
+    }
+
+    pub fn try_insert(
+        &mut self,
+        host: &str,
+        router: HttpRouter<T>,
+    ) -> Result<&mut Self, RouterError> {
+        let pattern = HostPattern::parse(host).map_err(RouterError::new)?;
+        let exists = || RouterError::new("host pattern already exists");
+
+        if !pattern.wildcard && pattern.labels.iter().all(Label::is_static) {
+            let host: Box<str> = host.trim_end_matches('.').to_ascii_lowercase().into();
+            if self.exact.contains_key(&host) {
+                return Err(exists());
+            }
+            self.exact.insert(host, router);
+            return Ok(self);
+        }
+
+        if self.patterns.iter().any(|(p, _)| *p == pattern) {
+            return Err(exists());
+        }
+        // patterns with captures are tried before wildcards, both in insertion order
+        let pos = if pattern.wildcard {
+            self.patterns.len()
+        } else {
+            self.patterns
+                .iter()
+                .position(|(p, _)| p.wildcard)
+                .unwrap_or(self.patterns.len())
+        };
+        self.patterns.insert(pos, (pattern, router));
+        Ok(self)
+    }
+
+    /// Sets the router of the hosts matched by no pattern, and of the requests without a host.
+    pub fn set_default(&mut self, router: HttpRouter<T>) -> &mut Self {
+        self.default = Some(router);
+        self
+    }
+
+    pub fn find<'p, 's: 'p>(
+        &'s self,
+        method: &Method,
+        host: &'p str,
+        path: &'p str,
+    ) -> Option<(&'s T, Captures<'p>)> {
+        let (router, host_captures) = self.select(Some(host))?;
+        let (data, mut captures) = router.find(method, path)?;
+        captures.set_host(host, &host_captures);
+        Some((data, captures))
+    }
+
+    pub fn find_all<'p, 's: 'p>(
+        &'s self,
+        method: &Method,
+        host: &'p str,
+        path: &'p str,
+    ) -> Vec<(&'s T, Captures<'p>)> {
+        let (router, host_captures) = match self.select(Some(host)) {
+            Some(selected) => selected,
+            None => return Vec::new(),
+        };
+        let mut ans = router.find_all(method, path);
+        for (_, captures) in ans.iter_mut() {
+            captures.set_host(host, &host_captures);
+        }
+        ans
+    }
+
+    /// Returns all routers, including the default one.
+    #[cfg(feature = "hyper-service")]
+    pub(crate) fn routers(&self) -> impl Iterator<Item = &HttpRouter<T>> {
+        let patterns = self.patterns.iter().map(|(_, router)| router);
+        self.exact
+            .values()
+            .chain(patterns)
+            .chain(self.default.iter())
+    }
+
+    /// Selects the router of `host` and captures the labels of the host.
+    pub(crate) fn select<'h>(
+        &self,
+        host: Option<&'h str>,
+    ) -> Option<(&HttpRouter<T>, HostCaptures<'_, 'h>)> {
+        let host = match host.map(strip_port) {
+            Some(host) if !host.is_empty() => host,
+            _ => return self.default.as_ref().map(|r| (r, SmallVec::new())),
+        };
+
+        let exact = if host.bytes().any(|b| b.is_ascii_uppercase()) {
+            self.exact.get(&*host.to_ascii_lowercase())
+        } else {
+            self.exact.get(host)
+        };
+        if let Some(router) = exact {
+            return Some((router, SmallVec::new()));
+        }
+
+        let labels: SmallVec<[&str; 8]> = host.split('.').collect();
+        for (pattern, router) in self.patterns.iter() {
+            let mut captures = SmallVec::new();
+            if pattern.is_match(&labels, &mut captures) {
+                return Some((router, captures));
+            }
+        }
+        self.default.as_ref().map(|r| (r, SmallVec::new()))
+    }
+}
+
+impl Label {
+    fn is_static(&self) -> bool {
+        matches!(*self, Label::Static(_))
+    }
+}
+
+/// Two labels are equal if they are the same static label or both captures.
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Label::Static(lhs), Label::Static(rhs)) => lhs == rhs,
+            (Label::Capture(_), Label::Capture(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl HostPattern {
+    fn parse(host: &str) -> Result<Self, &'static str> {
+        let host = host.trim_end_matches('.');
+        if host.is_empty() {
+            return Err("host pattern can not be empty");
+        }
+        let (wildcard, rest) = match host.strip_prefix("*.") {
+            Some(rest) => (true, rest),
+            None => (false, host),
+        };
+
+        let mut labels = Vec::new();
+        for label in rest.split('.') {
+            let label = match label.strip_prefix(':') {
+                Some(name) => {
+                    if name.is_empty() {
+                        return Err("capture name can not be empty");
+                    }
+                    if !name.chars().all(is_name_char) {
+                        return Err("capture name can only contain ASCII letters, digits and '_'");
+                    }
+                    Label::Capture(name.into())
+                }
+                None if label.is_empty() => return Err("host label can not be empty"),
+                None if label.contains('*') => {
+                    return Err("wildcard can only appear as the first label")
+                }
+                None if label.contains(':') => return Err("host pattern can not contain a port"),
+                None => Label::Static(label.to_ascii_lowercase().into()),
+            };
+            labels.push(label);
+        }
+        Ok(Self { wildcard, labels })
+    }
+
+    fn is_match<'s, 'h>(&'s self, host: &[&'h str], captures: &mut HostCaptures<'s, 'h>) -> bool {
+        // a wildcard matches at least one label
+        let len_matched = match host.len().checked_sub(self.labels.len()) {
+            Some(0) => !self.wildcard,
+            Some(_) => self.wildcard,
+            None => false,
+        };
+        if !len_matched {
+            return false;
+        }
+        let host = &host[host.len() - self.labels.len()..];
+        for (label, &part) in self.labels.iter().zip(host) {
+            match *label {
+                Label::Static(ref s) if s.eq_ignore_ascii_case(part) => {}
+                Label::Capture(ref name) if !part.is_empty() => captures.push((&**name, part)),
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// Removes the port and the trailing dot of a host.
+fn strip_port(host: &str) -> &str {
+    let host = if host.starts_with('[') {
+        // an IPv6 address
+        match host.find(']') {
+            Some(i) => &host[..=i],
+            None => host,
+        }
+    } else {
+        host.rsplit_once(':').map_or(host, |(host, _)| host)
+    };
+    host.trim_end_matches('.')
+}
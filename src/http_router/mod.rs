@@ -1,4 +1,17 @@
+mod guard;
+mod host;
 mod router;
 mod router_macro;
 
+#[cfg(feature = "serde")]
+mod config;
+
+pub use self::guard::Guard;
+pub use self::host::HostRouter;
 pub use self::router::{HttpRouter, Method};
+
+#[cfg(feature = "hyper-service")]
+pub(crate) use self::router::Entry;
+
+#[cfg(feature = "serde")]
+pub use self::config::{ConfigError, NestedConfig, RouteConfig, RouterConfig};
@@ -1,27 +1,160 @@
-use crate::router::{Captures, Router, RouterError};
+use super::guard::Guard;
+use crate::router::{
+    normalize, Captures, CaseSensitivity, ParamType, PercentDecoding, Rejection, Router,
+    RouterError, TrailingSlash,
+};
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 pub use http::Method;
 
 #[derive(Debug, Default)]
 pub struct HttpRouter<T> {
-    method_map: HashMap<Method, Router<T>>,
+    method_map: HashMap<Method, Router<Entry<T>>>,
+    trailing_slash: TrailingSlash,
+    case: CaseSensitivity,
+    decoding: PercentDecoding,
+    normalization: bool,
 }
 
 impl<T> HttpRouter<T> {
     pub fn new() -> Self {
         Self {
             method_map: HashMap::new(),
+            trailing_slash: TrailingSlash::Strict,
+            case: CaseSensitivity::Sensitive,
+            decoding: PercentDecoding::Disabled,
+            normalization: false,
         }
     }
 
+    pub fn trailing_slash(&self) -> TrailingSlash {
+        self.trailing_slash
+    }
+
+    /// Sets the trailing slash policy of the routers of all methods.
+    pub fn set_trailing_slash(&mut self, policy: TrailingSlash) -> &mut Self {
+        self.trailing_slash = policy;
+        for router in self.method_map.values_mut() {
+            router.set_trailing_slash(policy);
+        }
+        self
+    }
+
+    pub fn case_sensitivity(&self) -> CaseSensitivity {
+        self.case
+    }
+
+    /// Sets the case sensitivity of the routers of all methods.
+    ///
+    /// # Panics
+    /// Panics if two routes of a method match the same paths in the new mode.
+    pub fn set_case_sensitivity(&mut self, case: CaseSensitivity) -> &mut Self {
+        if let Err(e) = self.try_set_case_sensitivity(case) {
+            panic!("{}: case = {:?}", e, case);
+        }
+        self
+    }
+
+    /// Sets the case sensitivity of the routers of all methods, and keeps the mode of all of
+    /// them if two routes of a method would match the same paths.
+    pub fn try_set_case_sensitivity(
+        &mut self,
+        case: CaseSensitivity,
+    ) -> Result<&mut Self, RouterError> {
+        let old = self.case;
+        for (done, router) in self.method_map.values_mut().enumerate() {
+            if let Err(e) = router.try_set_case_sensitivity(case) {
+                for router in self.method_map.values_mut().take(done) {
+                    router.set_case_sensitivity(old);
+                }
+                return Err(e);
+            }
+        }
+        self.case = case;
+        Ok(self)
+    }
+
+    pub fn percent_decoding(&self) -> PercentDecoding {
+        self.decoding
+    }
+
+    /// Sets the percent-decoding mode of the routers of all methods.
+    pub fn set_percent_decoding(&mut self, decoding: PercentDecoding) -> &mut Self {
+        self.decoding = decoding;
+        for router in self.method_map.values_mut() {
+            router.set_percent_decoding(decoding);
+        }
+        self
+    }
+
+    pub fn path_normalization(&self) -> bool {
+        self.normalization
+    }
+
+    /// Sets the path normalization of the routers of all methods.
+    pub fn set_path_normalization(&mut self, enabled: bool) -> &mut Self {
+        self.normalization = enabled;
+        for router in self.method_map.values_mut() {
+            router.set_path_normalization(enabled);
+        }
+        self
+    }
+
+    /// Returns the normalized path if normalization is enabled, or the path itself otherwise.
+    /// See [`Router::normalize_path`].
+    pub fn normalize_path<'p>(&self, path: &'p str) -> Option<Cow<'p, str>> {
+        if self.normalization {
+            normalize(path)
+        } else {
+            Some(Cow::Borrowed(path))
+        }
+    }
+
+    /// Returns the methods, patterns and data of all routes, ordered by method.
+    pub fn routes(&self) -> impl Iterator<Item = (&Method, String, &T)> + '_ {
+        self.route_entries()
+            .into_iter()
+            .map(|(method, pattern, _, data)| (method, pattern, data))
+    }
+
     pub fn find<'p, 's: 'p>(
         &'s self,
         method: &Method,
         path: &'p str,
     ) -> Option<(&'s T, Captures<'p>)> {
-        self.method_map.get(method)?.find(path)
+        let (entry, captures) = self.find_entry(method, path)?;
+        Some((entry.data(), captures))
+    }
+
+    /// Returns the matching route with its guards.
+    pub(crate) fn find_entry<'p, 's: 'p>(
+        &'s self,
+        method: &Method,
+        path: &'p str,
+    ) -> Option<(&'s Entry<T>, Captures<'p>)> {
+        match self.method_map.get(method).and_then(|r| r.find(path)) {
+            Some(found) => Some(found),
+            None => self.method_map.get(&any_method())?.find(path),
+        }
+    }
+
+    /// Returns the methods which have a route matching `path`, ordered by method.
+    ///
+    /// A path matched by none of the methods gets an empty list.
+    /// The routes of any method are not taken into account.
+    pub fn allowed_methods(&self, path: &str) -> Vec<&Method> {
+        let any = any_method();
+        let mut methods: Vec<&Method> = self
+            .method_map
+            .iter()
+            .filter(|(method, router)| **method != any && router.find(path).is_some())
+            .map(|(method, _)| method)
+            .collect();
+        methods.sort_by(|lhs, rhs| lhs.as_str().cmp(rhs.as_str()));
+        methods
     }
 
     pub fn find_mut<'p, 's: 'p>(
@@ -29,12 +162,58 @@ impl<T> HttpRouter<T> {
         method: &Method,
         path: &'p str,
     ) -> Option<(&'s mut T, Captures<'p>)> {
-        self.method_map.get_mut(method)?.find_mut(path)
+        let found = match self.method_map.get(method) {
+            Some(router) => router.find(path).is_some(),
+            None => false,
+        };
+        let method = if found { method.clone() } else { any_method() };
+        let (entry, captures) = self.method_map.get_mut(&method)?.find_mut(path)?;
+        Some((entry.data_mut(), captures))
+    }
+
+    pub fn find_all<'p, 's: 'p>(
+        &'s self,
+        method: &Method,
+        path: &'p str,
+    ) -> Vec<(&'s T, Captures<'p>)> {
+        let entries = self.find_entries(method, path);
+        entries
+            .into_iter()
+            .flat_map(|(e, c)| e.alternatives.iter().map(move |(t, _)| (t, c.clone())))
+            .collect()
+    }
+
+    /// Returns the matching routes with their guards in priority order.
+    pub(crate) fn find_entries<'p, 's: 'p>(
+        &'s self,
+        method: &Method,
+        path: &'p str,
+    ) -> Vec<(&'s Entry<T>, Captures<'p>)> {
+        let mut ans = match self.method_map.get(method) {
+            Some(router) => router.find_all(path),
+            None => Vec::new(),
+        };
+        let any = any_method();
+        if let Some(router) = self.method_map.get(&any).filter(|_| *method != any) {
+            ans.extend(router.find_all(path));
+        }
+        ans
+    }
+
+    pub fn explain(&self, method: &Method, path: &str) -> Vec<(String, Rejection)> {
+        let mut ans = match self.method_map.get(method) {
+            Some(router) => router.explain(path),
+            None => Vec::new(),
+        };
+        let any = any_method();
+        if let Some(router) = self.method_map.get(&any).filter(|_| *method != any) {
+            ans.extend(router.explain(path));
+        }
+        ans
     }
 
     pub fn insert(&mut self, method: Method, pattern: &str, data: T) -> &mut Self {
-        self.access_router(method).insert(pattern, data);
-        self
+        self.insert_guarded(method, pattern, Vec::new(), data)
     }
 
     pub fn try_insert(
@@ -43,10 +222,135 @@ impl<T> HttpRouter<T> {
         pattern: &str,
         data: T,
     ) -> Result<&mut Self, RouterError> {
-        self.access_router(method).try_insert(pattern, data)?;
+        self.try_insert_guarded(method, pattern, Vec::new(), data)
+    }
+
+    /// Inserts a route which is only taken by `RouterService` when all of its `guards` pass.
+    ///
+    /// Guarded routes can share a pattern, even with captures named differently, and are tried
+    /// in insertion order before the route without guards. `find` returns the route without guards,
+    /// or the first route if all of them have guards, and `find_all` returns all of them.
+    pub fn insert_guarded(
+        &mut self,
+        method: Method,
+        pattern: &str,
+        guards: Vec<Guard>,
+        data: T,
+    ) -> &mut Self {
+        if let Err(e) = self.insert_entry(method, pattern, guards, data) {
+            panic!("{}: pattern = {:?}", e, pattern);
+        }
+        self
+    }
+
+    pub fn try_insert_guarded(
+        &mut self,
+        method: Method,
+        pattern: &str,
+        guards: Vec<Guard>,
+        data: T,
+    ) -> Result<&mut Self, RouterError> {
+        self.insert_entry(method, pattern, guards, data)?;
         Ok(self)
     }
 
+    /// Inserts a route of any method.
+    ///
+    /// A route of the request method takes precedence over the routes of any method,
+    /// even if it is less specific. The routes of any method are listed under the method `*`.
+    pub fn any(&mut self, pattern: &str, data: T) -> &mut Self {
+        self.insert(any_method(), pattern, data)
+    }
+
+    pub fn try_any(&mut self, pattern: &str, data: T) -> Result<&mut Self, RouterError> {
+        self.try_insert(any_method(), pattern, data)
+    }
+
+    /// Inserts a named route. Route names are unique across all methods.
+    pub fn insert_named(
+        &mut self,
+        method: Method,
+        name: &str,
+        pattern: &str,
+        data: T,
+    ) -> &mut Self {
+        if let Err(e) = self.try_insert_named(method, name, pattern, data) {
+            panic!("{}: pattern = {:?}, name = {:?}", e, pattern, name);
+        }
+        self
+    }
+
+    pub fn try_insert_named(
+        &mut self,
+        method: Method,
+        name: &str,
+        pattern: &str,
+        data: T,
+    ) -> Result<&mut Self, RouterError> {
+        if self.has_name(name) {
+            return Err(RouterError::new("route name already exists"));
+        }
+        self.access_router(method).try_insert_named(
+            name,
+            pattern,
+            Entry::new(pattern, data, Box::new([])),
+        )?;
+        Ok(self)
+    }
+
+    fn has_name(&self, name: &str) -> bool {
+        self.method_map.values().any(|r| r.has_name(name))
+    }
+
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, RouterError> {
+        match self.method_map.values().find(|r| r.has_name(name)) {
+            Some(router) => router.url_for(name, params),
+            None => Err(RouterError::new("route name not found")),
+        }
+    }
+
+    pub fn params(&self, name: &str) -> Option<Vec<(&str, Option<ParamType>)>> {
+        self.method_map.values().find_map(|r| r.params(name))
+    }
+
+    /// Removes the route of `pattern`.
+    ///
+    /// If guarded routes share the pattern, only the route without guards is removed.
+    pub fn remove(&mut self, method: &Method, pattern: &str) -> Option<T> {
+        let router = self.method_map.get_mut(method)?;
+        let data = match router.data_mut_by_pattern(pattern) {
+            Some(entry) if entry.alternatives.len() > 1 => {
+                let i = entry.unguarded()?;
+                Some(entry.alternatives.remove(i).0)
+            }
+            _ => router.remove(pattern).map(Entry::into_data),
+        };
+        if router.is_empty() {
+            self.method_map.remove(method);
+        }
+        data
+    }
+
+    /// Removes all routes of `pattern`, including the guarded ones, in priority order.
+    pub fn remove_all(&mut self, method: &Method, pattern: &str) -> Vec<T> {
+        let router = match self.method_map.get_mut(method) {
+            Some(router) => router,
+            None => return Vec::new(),
+        };
+        let data = match router.remove(pattern) {
+            Some(entry) => entry.alternatives.into_iter().map(|(t, _)| t).collect(),
+            None => Vec::new(),
+        };
+        if router.is_empty() {
+            self.method_map.remove(method);
+        }
+        data
+    }
+
+    pub fn remove_any(&mut self, pattern: &str) -> Option<T> {
+        self.remove(&any_method(), pattern)
+    }
+
     pub fn nest(&mut self, prefix: &str, f: impl FnOnce(&mut HttpRouter<T>)) -> &mut Self {
         let mut sub_router = Self::new();
         f(&mut sub_router);
@@ -66,16 +370,24 @@ impl<T> HttpRouter<T> {
     }
 
     pub fn insert_router(&mut self, prefix: &str, router: HttpRouter<T>) {
-        for (method, router) in router.method_map {
-            self.access_router(method).insert_router(prefix, router);
+        if let Err(e) = self.try_insert_router(prefix, router) {
+            panic!("{}: pattern = {:?}", e, prefix);
         }
     }
 
     pub fn try_insert_router(
         &mut self,
         prefix: &str,
-        router: HttpRouter<T>,
+        mut router: HttpRouter<T>,
     ) -> Result<&mut Self, RouterError> {
+        let mut names = Vec::new();
+        for r in router.method_map.values() {
+            r.collect_names(&mut names);
+        }
+        if names.into_iter().any(|n| self.has_name(n)) {
+            return Err(RouterError::new("route name already exists"));
+        }
+        router.prefix_patterns(prefix);
         for (method, router) in router.method_map {
             self.access_router(method)
                 .try_insert_router(prefix, router)?;
@@ -84,8 +396,166 @@ impl<T> HttpRouter<T> {
     }
 }
 
+impl<T: Clone> HttpRouter<T> {
+    /// Inserts a route for each of `methods`.
+    pub fn insert_methods(&mut self, methods: &[Method], pattern: &str, data: T) -> &mut Self {
+        for method in methods {
+            self.insert(method.clone(), pattern, data.clone());
+        }
+        self
+    }
+
+    pub fn try_insert_methods(
+        &mut self,
+        methods: &[Method],
+        pattern: &str,
+        data: T,
+    ) -> Result<&mut Self, RouterError> {
+        for (i, method) in methods.iter().enumerate() {
+            if let Err(e) = self.try_insert(method.clone(), pattern, data.clone()) {
+                // the router is left unchanged
+                for method in methods[..i].iter() {
+                    self.remove(method, pattern);
+                }
+                return Err(e);
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// The data of the routes sharing a pattern, and their guards.
+#[derive(Debug)]
+pub(crate) struct Entry<T> {
+    /// The full pattern, including the prefixes of the nesting routers.
+    pub(crate) pattern: Arc<str>,
+    pub(crate) alternatives: Vec<(T, Box<[Guard]>)>,
+}
+
+impl<T> Entry<T> {
+    fn new(pattern: &str, data: T, guards: Box<[Guard]>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            alternatives: vec![(data, guards)],
+        }
+    }
+
+    /// Like `into_data`, prefers the route without guards.
+    fn data(&self) -> &T {
+        &self.alternatives[self.unguarded().unwrap_or(0)].0
+    }
+
+    fn data_mut(&mut self) -> &mut T {
+        let i = self.unguarded().unwrap_or(0);
+        &mut self.alternatives[i].0
+    }
+
+    /// Returns the data of the route without guards, or of the first route.
+    fn into_data(mut self) -> T {
+        let i = self.unguarded().unwrap_or(0);
+        self.alternatives.swap_remove(i).0
+    }
+
+    /// Returns the position of the route without guards.
+    fn unguarded(&self) -> Option<usize> {
+        self.alternatives.iter().position(|(_, g)| g.is_empty())
+    }
+}
+
+/// The method of the routes matching any method.
+fn any_method() -> Method {
+    Method::from_bytes(b"*").unwrap()
+}
+
 impl<T> HttpRouter<T> {
-    fn access_router(&mut self, method: Method) -> &mut Router<T> {
-        self.method_map.entry(method).or_insert_with(Router::new)
+    /// Calls `f` with the data of every route, including the nested ones.
+    #[cfg(feature = "hyper-service")]
+    pub(crate) fn for_each_data(&self, mut f: impl FnMut(&T)) {
+        for router in self.method_map.values() {
+            router.for_each_data(&mut |e| e.alternatives.iter().for_each(|(data, _)| f(data)));
+        }
+    }
+
+    /// Calls `f` with the data of every route, including the nested ones.
+    #[cfg(feature = "hyper-service")]
+    pub(crate) fn for_each_data_mut(&mut self, mut f: impl FnMut(&mut T)) {
+        for router in self.method_map.values_mut() {
+            router.for_each_data_mut(&mut |e| {
+                e.alternatives.iter_mut().for_each(|(data, _)| f(data))
+            });
+        }
+    }
+
+    fn prefix_patterns(&mut self, prefix: &str) {
+        for router in self.method_map.values_mut() {
+            router.for_each_data_mut(&mut |e| {
+                e.pattern = format!("{}{}", prefix, e.pattern).into();
+            });
+        }
+    }
+
+    pub(super) fn route_entries(&self) -> Vec<(&Method, String, Option<&str>, &T)> {
+        let mut methods: Vec<(&Method, &Router<Entry<T>>)> = self.method_map.iter().collect();
+        methods.sort_by(|lhs, rhs| lhs.0.as_str().cmp(rhs.0.as_str()));
+        methods
+            .into_iter()
+            .flat_map(|(method, router)| {
+                router
+                    .route_entries()
+                    .into_iter()
+                    .flat_map(move |(pattern, name, e)| {
+                        e.alternatives
+                            .iter()
+                            .map(move |(data, _)| (method, pattern.clone(), name, data))
+                    })
+            })
+            .collect()
+    }
+
+    /// Inserts a route, or adds it to the guarded routes sharing its pattern.
+    fn insert_entry(
+        &mut self,
+        method: Method,
+        pattern: &str,
+        guards: Vec<Guard>,
+        data: T,
+    ) -> Result<(), RouterError> {
+        let guards: Box<[Guard]> = guards.into();
+        let router = self.access_router(method.clone());
+        if let Some(entry) = router.data_mut_by_pattern(pattern) {
+            // guarded routes are tried before the route without guards
+            match (entry.unguarded(), guards.is_empty()) {
+                (Some(i), false) => {
+                    entry.alternatives.insert(i, (data, guards));
+                    return Ok(());
+                }
+                (None, _) => {
+                    entry.alternatives.push((data, guards));
+                    return Ok(());
+                }
+                (Some(_), true) => {}
+            }
+        }
+        if let Err(e) = router.try_insert(pattern, Entry::new(pattern, data, guards)) {
+            if router.is_empty() {
+                self.method_map.remove(&method);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn access_router(&mut self, method: Method) -> &mut Router<Entry<T>> {
+        let (policy, case) = (self.trailing_slash, self.case);
+        let (decoding, normalization) = (self.decoding, self.normalization);
+        self.method_map.entry(method).or_insert_with(|| {
+            let mut router = Router::new();
+            router
+                .set_trailing_slash(policy)
+                .set_case_sensitivity(case)
+                .set_percent_decoding(decoding)
+                .set_path_normalization(normalization);
+            router
+        })
     }
 }
@@ -1,11 +1,36 @@
 #[macro_export]
 macro_rules! http_router {
-    {$($method:tt $pattern:expr => $data:expr),+} => {{
+    {$($($method:tt)|+ $pattern:literal => $data:expr),+} => {{
         let mut __router = $crate::HttpRouter::new();
-        $(http_router!(@entry __router, $method, $pattern, $data);)+
+        $($crate::http_router!(@entries __router, [$($method)|+], $pattern, $data);)+
         __router
     }};
 
+    // patterns which are not literals are parsed one token at a time
+    {@munch $router:ident [$($methods:tt)+] | $method:tt $($rest:tt)*} => {
+        $crate::http_router!(@munch $router [$($methods)+ $method] $($rest)*)
+    };
+    {@munch $router:ident [$($methods:tt)+] $pattern:expr => $data:expr $(, $($rest:tt)*)?} => {{
+        $crate::http_router!(@entries $router, [$($methods)|+], $pattern, $data);
+        $crate::http_router!(@munch $router [] $($($rest)*)?)
+    }};
+    {@munch $router:ident []} => {
+        $router
+    };
+    {@munch $router:ident [] $method:tt $($rest:tt)*} => {
+        $crate::http_router!(@munch $router [$method] $($rest)*)
+    };
+
+    {@entries $router:expr, [$method:tt], $pattern:expr, $data:expr} => {
+        $crate::http_router!(@entry $router, $method, $pattern, $data)
+    };
+    {@entries $router:expr, [$($method:tt)|+], $pattern:expr, $data:expr} => {
+        $router.insert_methods(&[$($crate::Method::$method),+], $pattern, $data)
+    };
+
+    {@entry $router:expr, *, $pattern:expr, $data:expr} => {
+        $router.any($pattern, $data)
+    };
     {@entry $router:expr, @, $prefix:expr, $sub_router:expr} => {
         $router.insert_router($prefix, $sub_router)
     };
@@ -36,4 +61,9 @@ macro_rules! http_router {
     {@entry $router:expr, TRACE, $pattern:expr, $data:expr} => {
         $router.insert($crate::Method::TRACE, $pattern, $data)
     };
+
+    {$($tt:tt)+} => {{
+        let mut __router = $crate::HttpRouter::new();
+        $crate::http_router!(@munch __router [] $($tt)+)
+    }};
 }
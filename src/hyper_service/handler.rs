@@ -1,8 +1,18 @@
 use super::{BoxError, BoxFuture, Future, Request, Response, StdError};
 use crate::router::OwnedCaptures;
 
+use std::fmt;
+use std::task::{Context, Poll};
+
 pub trait Handler {
     fn call(&self, req: Request, params: OwnedCaptures) -> BoxFuture<'static, Result<Response, BoxError>>;
+
+    /// Returns `Poll::Ready(Ok(()))` when the handler is able to handle requests.
+    ///
+    /// `RouterService` is ready when all of its handlers are ready.
+    fn poll_ready(&self, _cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Poll::Ready(Ok(()))
+    }
 }
 
 pub type BoxHandler = Box<dyn Handler + Send + Sync>;
@@ -11,6 +21,10 @@ impl Handler for BoxHandler {
     fn call(&self, req: Request, params: OwnedCaptures) -> BoxFuture<'static, Result<Response, BoxError>> {
         Handler::call(&**self, req, params)
     }
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Handler::poll_ready(&**self, cx)
+    }
 }
 
 impl<F, E, Fut> Handler for F
@@ -30,3 +44,27 @@ where
         })
     }
 }
+
+/// An error returned by a handler to decline a request.
+///
+/// The request is passed to the handler of the next matching route, and finally to the default handler.
+#[derive(Debug)]
+pub struct Declined(Request);
+
+impl Declined {
+    pub fn new(req: Request) -> Self {
+        Self(req)
+    }
+
+    pub fn into_request(self) -> Request {
+        self.0
+    }
+}
+
+impl fmt::Display for Declined {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the handler declined the request")
+    }
+}
+
+impl StdError for Declined {}
@@ -0,0 +1,134 @@
+use super::handler::{BoxHandler, Handler};
+use super::{BoxError, BoxFuture, Request, Response};
+use crate::router::OwnedCaptures;
+
+use std::fmt;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// A hook around the handler of a route.
+///
+/// A middleware can inspect or modify the request before calling [`Next::run`],
+/// modify the response after it, or respond without calling the handler at all.
+///
+/// Middlewares are attached globally by [`RouterService::add_middleware`](crate::RouterService::add_middleware),
+/// to the routes of a router by [`HttpRouter::layer`](crate::HttpRouter::layer),
+/// or to a single route by [`HttpRouter::route_with`](crate::HttpRouter::route_with).
+pub trait Middleware {
+    fn call<'a>(
+        &'a self,
+        req: Request,
+        next: Next<'a>,
+    ) -> BoxFuture<'a, Result<Response, BoxError>>;
+}
+
+pub(crate) type BoxMiddleware = Arc<dyn Middleware + Send + Sync>;
+
+impl fmt::Debug for dyn Middleware + Send + Sync {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Middleware")
+    }
+}
+
+/// The rest of a middleware chain, ending with the handler.
+pub struct Next<'a> {
+    middlewares: &'a [BoxMiddleware],
+    handler: &'a (dyn Handler + Sync),
+    pattern: Option<RoutePattern>,
+    params: OwnedCaptures,
+}
+
+/// The full pattern of the matched route, stored in the extensions of a routed request.
+#[derive(Debug, Clone)]
+pub(crate) struct RoutePattern(pub(crate) Arc<str>);
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middlewares: &'a [BoxMiddleware],
+        handler: &'a (dyn Handler + Sync),
+        pattern: Option<RoutePattern>,
+        params: OwnedCaptures,
+    ) -> Self {
+        Self {
+            middlewares,
+            handler,
+            pattern,
+            params,
+        }
+    }
+
+    /// Returns the pattern of the matched route, or `None` when no route handles the request.
+    pub fn pattern(&self) -> Option<&str> {
+        self.pattern.as_ref().map(|p| &*p.0)
+    }
+
+    pub fn params(&self) -> &OwnedCaptures {
+        &self.params
+    }
+
+    /// Calls the next middleware, or the handler at the end of the chain.
+    pub fn run(self, req: Request) -> BoxFuture<'a, Result<Response, BoxError>> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next {
+                    middlewares: rest,
+                    ..self
+                };
+                middleware.call(req, next)
+            }
+            None => Handler::call(self.handler, req, self.params),
+        }
+    }
+}
+
+impl fmt::Debug for Next<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Next")
+            .field("pattern", &self.pattern())
+            .field("params", &self.params)
+            .finish()
+    }
+}
+
+/// A handler wrapped by a middleware.
+pub(crate) struct Layered {
+    middleware: BoxMiddleware,
+    handler: Arc<BoxHandler>,
+}
+
+impl Layered {
+    pub(crate) fn new(middleware: BoxMiddleware, handler: BoxHandler) -> Self {
+        Self {
+            middleware,
+            handler: Arc::new(handler),
+        }
+    }
+}
+
+impl Handler for Layered {
+    fn call(
+        &self,
+        req: Request,
+        params: OwnedCaptures,
+    ) -> BoxFuture<'static, Result<Response, BoxError>> {
+        let (middleware, handler) = (Arc::clone(&self.middleware), Arc::clone(&self.handler));
+        Box::pin(async move {
+            let pattern = req.extensions().get::<RoutePattern>().cloned();
+            let next = Next::new(&[], &*handler, pattern, params);
+            middleware.call(req, next).await
+        })
+    }
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Handler::poll_ready(&**self.handler, cx)
+    }
+}
+
+/// Takes the place of a handler while it is being wrapped.
+pub(crate) struct Placeholder;
+
+impl Handler for Placeholder {
+    fn call(&self, _: Request, _: OwnedCaptures) -> BoxFuture<'static, Result<Response, BoxError>> {
+        unreachable!()
+    }
+}
@@ -1,10 +1,18 @@
 mod handler;
+mod middleware;
 mod service;
 mod service_macro;
 
-pub use self::handler::Handler;
+#[cfg(feature = "tower")]
+mod tower;
+
+pub use self::handler::{Declined, Handler};
+pub use self::middleware::{Middleware, Next};
 pub use self::service::{RouterService, SharedRouterService};
 
+#[cfg(feature = "tower")]
+pub use self::tower::{HandlerService, ServiceHandler};
+
 use std::error::Error as StdError;
 use std::future::Future;
 use std::pin::Pin;
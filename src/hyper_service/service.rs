@@ -1,18 +1,34 @@
-use super::handler::{BoxHandler, Handler};
+use super::handler::{BoxHandler, Declined, Handler};
+use super::middleware::{BoxMiddleware, Layered, Middleware, Next, Placeholder, RoutePattern};
 use super::{BoxError, BoxFuture, Request, Response};
-use crate::router::OwnedCaptures;
+use crate::router::{Captures, OwnedCaptures};
+use crate::OwnedQueryParams;
 
-use crate::http_router::{HttpRouter, Method};
+use crate::http_router::{Entry, Guard, HostRouter, HttpRouter, Method};
 
+use std::borrow::Cow;
+use std::mem;
+use std::ptr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
+use hyper::header::{HeaderValue, ALLOW, HOST, LOCATION};
 use hyper::service::Service;
+use hyper::{Body, StatusCode};
 
 #[derive(Debug)]
 pub struct RouterService<H = BoxHandler> {
-    router: HttpRouter<H>,
+    inner: Arc<Inner<H>>,
+}
+
+#[derive(Debug)]
+struct Inner<H> {
+    router: HostRouter<H>,
     default: H,
+    method_not_allowed: Option<H>,
+    auto_options: bool,
+    auto_head: bool,
+    middlewares: Vec<BoxMiddleware>,
 }
 
 #[derive(Debug)]
@@ -26,14 +42,14 @@ impl<H> Clone for SharedRouterService<H> {
 
 impl<H> Service<Request> for RouterService<H>
 where
-    H: Handler + Send + Sync,
+    H: Handler + Send + Sync + 'static,
 {
     type Response = Response;
     type Error = BoxError;
     type Future = BoxFuture<'static, Result<Response, BoxError>>;
 
-    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
@@ -43,14 +59,14 @@ where
 
 impl<H> Service<Request> for SharedRouterService<H>
 where
-    H: Handler + Send + Sync,
+    H: Handler + Send + Sync + 'static,
 {
     type Response = Response;
     type Error = BoxError;
     type Future = BoxFuture<'static, Result<Response, BoxError>>;
 
-    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        Poll::Ready(Ok(()))
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.0.inner.poll_ready(cx)
     }
 
     fn call(&mut self, req: Request) -> Self::Future {
@@ -60,24 +76,87 @@ where
 
 impl<H> RouterService<H>
 where
-    H: Handler,
+    H: Handler + Send + Sync + 'static,
 {
     fn handle(&self, req: Request) -> BoxFuture<'static, Result<Response, BoxError>> {
-        let method = req.method();
-        let path = req.uri().path();
-        let (handler, params) = match self.router.find(method, path) {
-            Some((h, caps)) => (h, OwnedCaptures::new(&caps)),
-            None => (&self.default, OwnedCaptures::empty()),
-        };
-        Handler::call(handler, req, params)
+        let inner = Arc::clone(&self.inner);
+        Box::pin(async move { inner.dispatch(req).await })
     }
+}
 
+impl<H> RouterService<H> {
     pub fn new(default: H) -> Self {
         Self::from_router(HttpRouter::new(), default)
     }
 
     pub fn from_router(router: HttpRouter<H>, default: H) -> Self {
-        Self { router, default }
+        let mut hosts = HostRouter::new();
+        hosts.set_default(router);
+        Self::from_host_router(hosts, default)
+    }
+
+    /// Creates a service which selects the router of a request by its host.
+    pub fn from_host_router(router: HostRouter<H>, default: H) -> Self {
+        let inner = Arc::new(Inner {
+            router,
+            default,
+            method_not_allowed: None,
+            auto_options: false,
+            auto_head: false,
+            middlewares: Vec::new(),
+        });
+        Self { inner }
+    }
+
+    /// Sets the handler of requests whose path only matches the routes of other methods.
+    ///
+    /// The `Allow` header is added to its response unless the handler sets one.
+    ///
+    /// # Panics
+    /// Panics if the service is handling requests.
+    pub fn set_method_not_allowed(&mut self, handler: H) -> &mut Self {
+        self.inner_mut().method_not_allowed = Some(handler);
+        self
+    }
+
+    /// Answers `OPTIONS` requests without an explicit route with the `Allow` header
+    /// listing the methods of the matched path.
+    ///
+    /// # Panics
+    /// Panics if the service is handling requests.
+    pub fn set_auto_options(&mut self, enabled: bool) -> &mut Self {
+        self.inner_mut().auto_options = enabled;
+        self
+    }
+
+    /// Serves `HEAD` requests without an explicit route by the `GET` handler, stripping the body.
+    ///
+    /// # Panics
+    /// Panics if the service is handling requests.
+    pub fn set_auto_head(&mut self, enabled: bool) -> &mut Self {
+        self.inner_mut().auto_head = enabled;
+        self
+    }
+
+    /// Adds a middleware around the handlers of all routes, the default handler,
+    /// and the responses of the service itself such as `405 Method Not Allowed`.
+    ///
+    /// Middlewares are called in the order they are added, before the ones attached to routers and routes.
+    /// The chain is called again for each handler tried after a handler declines.
+    ///
+    /// # Panics
+    /// Panics if the service is handling requests.
+    pub fn add_middleware(
+        &mut self,
+        middleware: impl Middleware + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.inner_mut().middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner<H> {
+        Arc::get_mut(&mut self.inner)
+            .expect("can not configure a service while it is handling requests")
     }
 
     pub fn into_shared(self) -> SharedRouterService<H> {
@@ -85,6 +164,314 @@ where
     }
 }
 
+impl<H> Inner<H>
+where
+    H: Handler + Sync,
+{
+    /// Polls the readiness of all handlers, which is pending if any of them is pending.
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        let mut pending = false;
+        let mut ans = Ok(());
+        let mut poll = |handler: &H| {
+            if ans.is_ok() {
+                match Handler::poll_ready(handler, cx) {
+                    Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(e)) => ans = Err(e),
+                    Poll::Pending => pending = true,
+                }
+            }
+        };
+        for router in self.router.routers() {
+            router.for_each_data(&mut poll);
+        }
+        poll(&self.default);
+        if let Some(ref handler) = self.method_not_allowed {
+            poll(handler);
+        }
+        match ans {
+            Ok(()) if pending => Poll::Pending,
+            ans => Poll::Ready(ans),
+        }
+    }
+
+    /// Calls the handlers of the matching routes in priority order until one of them does not decline.
+    async fn dispatch(&self, mut req: Request) -> Result<Response, BoxError> {
+        let host = request_host(&req).map(ToOwned::to_owned);
+        let (router, host_captures) = match self.router.select(host.as_deref()) {
+            Some(selected) => selected,
+            None => return self.call_default(req).await,
+        };
+
+        let path = match router.normalize_path(req.uri().path()) {
+            Some(Cow::Borrowed(path)) => path.to_owned(),
+            Some(Cow::Owned(normalized)) => {
+                return self.redirect_normalized(req, router, &normalized).await
+            }
+            None => return self.call_default(req).await,
+        };
+        let method = self.lookup_method(router, req.method(), &path);
+
+        let (entry, mut caps) = match router.find_entry(&method, &path) {
+            Some(found) => found,
+            None => {
+                let allowed = self.allowed_methods(router, &path);
+                if allowed.is_empty() {
+                    return self.call_default(req).await;
+                } else if self.auto_options && method == Method::OPTIONS {
+                    let handler = Allow::new(&Status(StatusCode::NO_CONTENT), allowed);
+                    return self.call_unrouted(req, &handler).await;
+                } else {
+                    return self.reject_method(req, allowed).await;
+                }
+            }
+        };
+        if let Some(location) = caps.redirect() {
+            return self.call_unrouted(req, &Redirect(location)).await;
+        }
+        if let Some(ref host) = host {
+            caps.set_host(host, &host_captures);
+        }
+        let query = OwnedQueryParams::from_request(&req);
+        req.extensions_mut().insert(query);
+
+        let strip_body = method != *req.method();
+        let mut rejection = None;
+        let mut called = false;
+        let mut ret = self
+            .call_entry(req, entry, &caps, &mut rejection, &mut called)
+            .await?;
+
+        // the other matching routes are only searched when the handlers of the first one decline
+        if ret.is_err() {
+            let path = caps.canonical_path().unwrap_or(&path);
+            let all = router.find_entries(&method, path);
+            for (e, mut caps) in all.into_iter().filter(|(e, _)| !ptr::eq(*e, entry)) {
+                let req = match ret {
+                    Ok(_) => break,
+                    Err(req) => req,
+                };
+                if let Some(ref host) = host {
+                    caps.set_host(host, &host_captures);
+                }
+                ret = self
+                    .call_entry(req, e, &caps, &mut rejection, &mut called)
+                    .await?;
+            }
+        }
+
+        let mut res = match ret {
+            Ok(res) => res,
+            Err(req) => match rejection {
+                Some(status) if !called => self.call_unrouted(req, &Status(status)).await?,
+                _ => self.call_default(req).await?,
+            },
+        };
+        if strip_body {
+            *res.body_mut() = Body::empty();
+        }
+        Ok(res)
+    }
+
+    /// Redirects to the normalized path if any route matches it.
+    async fn redirect_normalized(
+        &self,
+        req: Request,
+        router: &HttpRouter<H>,
+        normalized: &str,
+    ) -> Result<Response, BoxError> {
+        let method = self.lookup_method(router, req.method(), normalized);
+        if let Some((_, caps)) = router.find(&method, normalized) {
+            let location = caps.canonical_path().unwrap_or(normalized);
+            return self.call_unrouted(req, &Redirect(location)).await;
+        }
+        if !router.allowed_methods(normalized).is_empty() {
+            return self.call_unrouted(req, &Redirect(normalized)).await;
+        }
+        self.call_default(req).await
+    }
+
+    /// Calls the handlers of a route whose guards pass, and gives back the request if all of them decline.
+    ///
+    /// The status of the first failed guard with a rejection status is kept in `rejection`.
+    async fn call_entry(
+        &self,
+        mut req: Request,
+        entry: &Entry<H>,
+        caps: &Captures<'_>,
+        rejection: &mut Option<StatusCode>,
+        called: &mut bool,
+    ) -> Result<Result<Response, Request>, BoxError> {
+        for (handler, guards) in entry.alternatives.iter() {
+            if !guards.is_empty() {
+                let (parts, body) = req.into_parts();
+                let failed = guards.iter().find(|g| !g.check(&parts));
+                req = Request::from_parts(parts, body);
+                if let Some(guard) = failed {
+                    *rejection = rejection.or_else(|| guard.rejection());
+                    continue;
+                }
+            }
+            *called = true;
+            let pattern = RoutePattern(Arc::clone(&entry.pattern));
+            req.extensions_mut().insert(pattern.clone());
+            let params = OwnedCaptures::new(caps);
+            match Next::new(&self.middlewares, handler, Some(pattern), params)
+                .run(req)
+                .await
+            {
+                Err(e) => match e.downcast::<Declined>() {
+                    Ok(declined) => req = declined.into_request(),
+                    Err(e) => return Err(e),
+                },
+                Ok(res) => return Ok(Ok(res)),
+            }
+        }
+        Ok(Err(req))
+    }
+
+    async fn call_default(&self, req: Request) -> Result<Response, BoxError> {
+        self.call_unrouted(req, &self.default).await
+    }
+
+    /// Calls a handler which is not the one of a route through the middlewares.
+    async fn call_unrouted(
+        &self,
+        mut req: Request,
+        handler: &(dyn Handler + Sync),
+    ) -> Result<Response, BoxError> {
+        req.extensions_mut().remove::<RoutePattern>();
+        let query = OwnedQueryParams::from_request(&req);
+        req.extensions_mut().insert(query);
+        let next = Next::new(&self.middlewares, handler, None, OwnedCaptures::empty());
+        next.run(req).await
+    }
+
+    /// Returns the method whose routes serve the request.
+    fn lookup_method(&self, router: &HttpRouter<H>, method: &Method, path: &str) -> Method {
+        if self.auto_head
+            && *method == Method::HEAD
+            && router.find(&Method::HEAD, path).is_none()
+            && router.find(&Method::GET, path).is_some()
+        {
+            return Method::GET;
+        }
+        method.clone()
+    }
+
+    /// Returns the methods allowed on `path`, including the automatic ones.
+    fn allowed_methods(&self, router: &HttpRouter<H>, path: &str) -> Vec<Method> {
+        let mut allowed: Vec<Method> = router.allowed_methods(path).into_iter().cloned().collect();
+        if allowed.is_empty() {
+            return allowed;
+        }
+        if self.auto_head && allowed.contains(&Method::GET) && !allowed.contains(&Method::HEAD) {
+            allowed.push(Method::HEAD);
+        }
+        if self.auto_options && !allowed.contains(&Method::OPTIONS) {
+            allowed.push(Method::OPTIONS);
+        }
+        allowed.sort_by(|lhs, rhs| lhs.as_str().cmp(rhs.as_str()));
+        allowed
+    }
+
+    /// Responds `405 Method Not Allowed` with the `Allow` header.
+    async fn reject_method(
+        &self,
+        req: Request,
+        allowed: Vec<Method>,
+    ) -> Result<Response, BoxError> {
+        let status = Status(StatusCode::METHOD_NOT_ALLOWED);
+        let handler: &(dyn Handler + Sync) = match self.method_not_allowed {
+            Some(ref handler) => handler,
+            None => &status,
+        };
+        self.call_unrouted(req, &Allow::new(handler, allowed)).await
+    }
+}
+
+/// Responds with an empty body and a status.
+struct Status(StatusCode);
+
+impl Handler for Status {
+    fn call(&self, _: Request, _: OwnedCaptures) -> BoxFuture<'static, Result<Response, BoxError>> {
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = self.0;
+        Box::pin(async move { Ok(res) })
+    }
+}
+
+/// Adds the `Allow` header to the response of a handler unless it sets one.
+struct Allow<'a> {
+    handler: &'a (dyn Handler + Sync),
+    allowed: Vec<Method>,
+}
+
+impl<'a> Allow<'a> {
+    fn new(handler: &'a (dyn Handler + Sync), allowed: Vec<Method>) -> Self {
+        Self { handler, allowed }
+    }
+}
+
+impl Handler for Allow<'_> {
+    fn call(
+        &self,
+        req: Request,
+        params: OwnedCaptures,
+    ) -> BoxFuture<'static, Result<Response, BoxError>> {
+        let fut = Handler::call(self.handler, req, params);
+        let allowed = self.allowed.clone();
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if !res.headers().contains_key(ALLOW) {
+                set_allow(&mut res, &allowed);
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Returns the host of the request from the URI authority or the `Host` header.
+fn request_host(req: &Request) -> Option<&str> {
+    match req.uri().authority() {
+        Some(authority) => Some(authority.host()),
+        None => req.headers().get(HOST)?.to_str().ok(),
+    }
+}
+
+fn set_allow(res: &mut Response, allowed: &[Method]) {
+    let allowed: Vec<&str> = allowed.iter().map(|m| m.as_str()).collect();
+    if let Ok(value) = HeaderValue::from_str(&allowed.join(", ")) {
+        res.headers_mut().insert(ALLOW, value);
+    }
+}
+
+/// Redirects to the canonical path, keeping the query.
+///
+/// `301` is used for `GET` and `HEAD`, and `308` for other methods so that they are not changed.
+struct Redirect<'a>(&'a str);
+
+impl Handler for Redirect<'_> {
+    fn call(
+        &self,
+        req: Request,
+        _: OwnedCaptures,
+    ) -> BoxFuture<'static, Result<Response, BoxError>> {
+        let location = match req.uri().query() {
+            Some(query) => format!("{}?{}", self.0, query),
+            None => self.0.to_owned(),
+        };
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = match *req.method() {
+            Method::GET | Method::HEAD => StatusCode::MOVED_PERMANENTLY,
+            _ => StatusCode::PERMANENT_REDIRECT,
+        };
+        if let Ok(value) = HeaderValue::from_str(&location) {
+            res.headers_mut().insert(LOCATION, value);
+        }
+        Box::pin(async move { Ok(res) })
+    }
+}
+
 impl HttpRouter<BoxHandler> {
     pub fn route(
         &mut self,
@@ -95,11 +482,87 @@ impl HttpRouter<BoxHandler> {
         self.insert(method, path, Box::new(h))
     }
 
+    /// Routes each of `methods` to the same handler.
+    pub fn route_methods(
+        &mut self,
+        methods: &[Method],
+        path: &str,
+        h: impl Handler + Send + Sync + 'static,
+    ) -> &mut Self {
+        let h = Arc::new(h);
+        for method in methods {
+            self.route(method.clone(), path, SharedHandler(Arc::clone(&h)));
+        }
+        self
+    }
+
+    /// Routes the method to the handler when all of the `guards` pass. See [`Guard`].
+    pub fn route_guarded(
+        &mut self,
+        method: Method,
+        path: &str,
+        guards: Vec<Guard>,
+        h: impl Handler + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.insert_guarded(method, path, guards, Box::new(h))
+    }
+
+    /// Routes the method to the handler wrapped by `middleware`.
+    pub fn route_with(
+        &mut self,
+        method: Method,
+        path: &str,
+        middleware: impl Middleware + Send + Sync + 'static,
+        h: impl Handler + Send + Sync + 'static,
+    ) -> &mut Self {
+        let h = Layered::new(Arc::new(middleware), Box::new(h));
+        self.route(method, path, h)
+    }
+
+    /// Wraps the handlers of all routes inserted so far with `middleware`.
+    ///
+    /// Calling it at the end of [`HttpRouter::nest`] attaches the middleware to the nested routes.
+    pub fn layer(&mut self, middleware: impl Middleware + Send + Sync + 'static) -> &mut Self {
+        let middleware: BoxMiddleware = Arc::new(middleware);
+        self.for_each_data_mut(|h| {
+            let inner = mem::replace(h, Box::new(Placeholder));
+            *h = Box::new(Layered::new(Arc::clone(&middleware), inner));
+        });
+        self
+    }
+
+    /// Routes any method to the handler. See [`HttpRouter::any`].
+    pub fn route_any(&mut self, path: &str, h: impl Handler + Send + Sync + 'static) -> &mut Self {
+        self.any(path, Box::new(h))
+    }
+
     pub fn with_default(self, default: impl Handler + Send + Sync + 'static) -> RouterService {
         RouterService::from_router(self, Box::new(default))
     }
 }
 
+impl HostRouter<BoxHandler> {
+    pub fn with_default(self, default: impl Handler + Send + Sync + 'static) -> RouterService {
+        RouterService::from_host_router(self, Box::new(default))
+    }
+}
+
+struct SharedHandler<H>(Arc<H>);
+
+impl<H: Handler> Handler for SharedHandler<H> {
+    fn call(
+        &self,
+        req: Request,
+        params: OwnedCaptures,
+    ) -> BoxFuture<'static, Result<Response, BoxError>> {
+        Handler::call(&*self.0, req, params)
+    }
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        Handler::poll_ready(&*self.0, cx)
+    }
+}
+
 macro_rules! define_method{
     ($name:tt,$method:tt) => {
         pub fn $name(&mut self,path: &str,h: impl Handler+Send+Sync+'static) -> &mut Self{
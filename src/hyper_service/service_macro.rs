@@ -1,17 +1,46 @@
 #[macro_export]
 macro_rules! router_service {
-    {$($method:tt $pattern:expr => $data:expr),+ ; _ => $default:expr} => {{
+    {$($($method:tt)|+ $pattern:literal => $data:expr),+ ; _ => $default:expr} => {{
         let mut __router = $crate::HttpRouter::new();
-        $(router_service!(@entry __router, $method, $pattern, $data);)+
+        $($crate::router_service!(@entries __router, [$($method)|+], $pattern, $data);)+
         __router.with_default($default)
     }};
 
-    {$($method:tt $pattern:expr => $data:expr),+} => {{
+    {$($($method:tt)|+ $pattern:literal => $data:expr),+} => {{
         let mut __router = $crate::HttpRouter::new();
-        $(router_service!(@entry __router, $method, $pattern, $data);)+
+        $($crate::router_service!(@entries __router, [$($method)|+], $pattern, $data);)+
         __router
     }};
 
+    // patterns which are not literals are parsed one token at a time
+    {@munch $router:ident [$($methods:tt)+] | $method:tt $($rest:tt)*} => {
+        $crate::router_service!(@munch $router [$($methods)+ $method] $($rest)*)
+    };
+    {@munch $router:ident [$($methods:tt)+] $pattern:expr => $data:expr ; _ => $default:expr} => {{
+        $crate::router_service!(@entries $router, [$($methods)|+], $pattern, $data);
+        $router.with_default($default)
+    }};
+    {@munch $router:ident [$($methods:tt)+] $pattern:expr => $data:expr $(, $($rest:tt)*)?} => {{
+        $crate::router_service!(@entries $router, [$($methods)|+], $pattern, $data);
+        $crate::router_service!(@munch $router [] $($($rest)*)?)
+    }};
+    {@munch $router:ident []} => {
+        $router
+    };
+    {@munch $router:ident [] $method:tt $($rest:tt)*} => {
+        $crate::router_service!(@munch $router [$method] $($rest)*)
+    };
+
+    {@entries $router:expr, [$method:tt], $pattern:expr, $data:expr} => {
+        $crate::router_service!(@entry $router, $method, $pattern, $data)
+    };
+    {@entries $router:expr, [$($method:tt)|+], $pattern:expr, $data:expr} => {
+        $router.route_methods(&[$($crate::Method::$method),+], $pattern, $data)
+    };
+
+    {@entry $router:expr, *, $pattern:expr, $data:expr} => {
+        $router.route_any($pattern, $data)
+    };
     {@entry $router:expr, @, $prefix:expr, $sub_router:expr} => {
         $router.nest($prefix, |__r| *__r = $sub_router)
     };
@@ -42,4 +71,9 @@ macro_rules! router_service {
     {@entry $router:expr, TRACE, $pattern:expr, $data:expr} => {
         $router.route($crate::Method::TRACE, $pattern, $data)
     };
+
+    {$($tt:tt)+} => {{
+        let mut __router = $crate::HttpRouter::new();
+        $crate::router_service!(@munch __router [] $($tt)+)
+    }};
 }
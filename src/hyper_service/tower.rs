@@ -0,0 +1,132 @@
+use super::handler::{BoxHandler, Handler};
+use super::middleware::Placeholder;
+use super::{BoxError, BoxFuture, Request, Response};
+use crate::http_router::{HttpRouter, Method};
+use crate::router::OwnedCaptures;
+
+use std::future;
+use std::mem;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::task::{Context, Poll};
+
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// A handler which calls a tower service.
+///
+/// The captures are stored in the extensions of the request as [`OwnedCaptures`].
+/// The service is polled by [`Handler::poll_ready`], and each request is served by the polled
+/// instance while a clone of it takes its place.
+#[derive(Debug)]
+pub struct ServiceHandler<S> {
+    service: Mutex<S>,
+}
+
+impl<S> ServiceHandler<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Mutex::new(service),
+        }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.service
+            .into_inner()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, S> {
+        self.service.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<S> Handler for ServiceHandler<S>
+where
+    S: Service<Request, Response = Response> + Clone + Send + 'static,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    fn call(
+        &self,
+        mut req: Request,
+        params: OwnedCaptures,
+    ) -> BoxFuture<'static, Result<Response, BoxError>> {
+        req.extensions_mut().insert(params);
+        let mut service = {
+            let mut guard = self.lock();
+            let clone = guard.clone();
+            mem::replace(&mut *guard, clone)
+        };
+        Box::pin(async move {
+            future::poll_fn(|cx| service.poll_ready(cx))
+                .await
+                .map_err(Into::into)?;
+            service.call(req).await.map_err(Into::into)
+        })
+    }
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.lock().poll_ready(cx).map_err(Into::into)
+    }
+}
+
+/// A tower service which calls a handler, taking the captures from the extensions of the request.
+#[derive(Debug)]
+pub struct HandlerService<H>(Arc<H>);
+
+impl<H> HandlerService<H> {
+    pub fn new(handler: H) -> Self {
+        Self(Arc::new(handler))
+    }
+}
+
+impl<H> Clone for HandlerService<H> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<H: Handler> Service<Request> for HandlerService<H> {
+    type Response = Response;
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response, BoxError>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Handler::poll_ready(&*self.0, cx)
+    }
+
+    fn call(&mut self, mut req: Request) -> Self::Future {
+        let params = req.extensions_mut().remove::<OwnedCaptures>();
+        Handler::call(&*self.0, req, params.unwrap_or_else(OwnedCaptures::empty))
+    }
+}
+
+impl HttpRouter<BoxHandler> {
+    /// Routes the method to a tower service. See [`ServiceHandler`].
+    pub fn route_service<S>(&mut self, method: Method, path: &str, service: S) -> &mut Self
+    where
+        S: Service<Request, Response = Response> + Clone + Send + 'static,
+        S::Error: Into<BoxError>,
+        S::Future: Send + 'static,
+    {
+        self.route(method, path, ServiceHandler::new(service))
+    }
+
+    /// Wraps the handlers of all routes inserted so far with a tower layer.
+    ///
+    /// Calling it at the end of [`HttpRouter::nest`] applies the layer to the nested routes.
+    pub fn service_layer<L>(&mut self, layer: L) -> &mut Self
+    where
+        L: Layer<HandlerService<BoxHandler>>,
+        L::Service: Service<Request, Response = Response> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Error: Into<BoxError>,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        self.for_each_data_mut(|h| {
+            let inner = mem::replace(h, Box::new(Placeholder));
+            let service = layer.layer(HandlerService::new(inner));
+            *h = Box::new(ServiceHandler::new(service));
+        });
+        self
+    }
+}
@@ -7,10 +7,17 @@
 #![deny(unsafe_code)]
 
 mod bitset;
+mod percent;
 mod strmap;
 
+mod query;
+pub use crate::query::{OwnedQueryParams, QueryParams};
+
 mod router;
-pub use crate::router::{Captures, OwnedCaptures, Router, RouterError};
+pub use crate::router::{
+    Captures, CaseSensitivity, EncodedSlash, OwnedCaptures, ParamType, PercentDecoding, Rejection,
+    Router, RouterError, TrailingSlash,
+};
 
 macro_rules! cfg_feature{
     ($feature:literal; $($item:item)*)=>{
@@ -21,14 +28,29 @@ macro_rules! cfg_feature{
     }
 }
 
+cfg_feature! {
+    "serde";
+    pub use crate::router::DeserializeError;
+}
+
 cfg_feature! {
     "http-router";
     mod http_router;
-    pub use crate::http_router::{HttpRouter, Method};
+    pub use crate::http_router::{Guard, HostRouter, HttpRouter, Method};
 }
 
+#[cfg(all(feature = "http-router", feature = "serde"))]
+pub use crate::http_router::{ConfigError, NestedConfig, RouteConfig, RouterConfig};
+
 cfg_feature! {
     "hyper-service";
     mod hyper_service;
-    pub use crate::hyper_service::{RouterService, Handler, SharedRouterService};
+    pub use crate::hyper_service::{
+        Declined, Handler, Middleware, Next, RouterService, SharedRouterService,
+    };
+}
+
+cfg_feature! {
+    "tower";
+    pub use crate::hyper_service::{HandlerService, ServiceHandler};
 }
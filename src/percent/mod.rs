@@ -0,0 +1,71 @@
+use std::borrow::Cow;
+
+const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Returns true if the byte can appear in a path segment without encoding.
+#[inline]
+fn is_pchar(b: u8) -> bool {
+    matches!(b,
+        b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9'
+        | b'-' | b'.' | b'_' | b'~'
+        | b'!' | b'$' | b'&' | b'\'' | b'(' | b')' | b'*' | b'+' | b',' | b';' | b'='
+        | b':' | b'@'
+    )
+}
+
+/// Percent-encodes a path segment and appends it to `buf`.
+pub fn encode_segment(s: &str, buf: &mut String) {
+    for &b in s.as_bytes() {
+        if is_pchar(b) {
+            buf.push(b as char);
+        } else {
+            buf.push('%');
+            buf.push(HEX[(b >> 4) as usize] as char);
+            buf.push(HEX[(b & 0xf) as usize] as char);
+        }
+    }
+}
+
+#[inline]
+fn hex_value(b: u8) -> Option<u8> {
+    match b {
+        b'0'..=b'9' => Some(b - b'0'),
+        b'A'..=b'F' => Some(b - b'A' + 10),
+        b'a'..=b'f' => Some(b - b'a' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes `%XX` sequences. Malformed sequences are left as they are.
+///
+/// `%2F` is left encoded if `keep_slash` is true.
+pub fn decode(s: &str, keep_slash: bool) -> Cow<'_, [u8]> {
+    if !s.contains('%') {
+        return Cow::Borrowed(s.as_bytes());
+    }
+    let bytes = s.as_bytes();
+    let mut buf = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(h), Some(l)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                let b = (h << 4) | l;
+                if !(keep_slash && b == b'/') {
+                    buf.push(b);
+                    i += 3;
+                    continue;
+                }
+            }
+        }
+        buf.push(bytes[i]);
+        i += 1;
+    }
+    Cow::Owned(buf)
+}
+
+/// Returns true if the path contains an encoded slash.
+pub fn has_encoded_slash(s: &str) -> bool {
+    s.as_bytes()
+        .windows(3)
+        .any(|w| w[0] == b'%' && w[1] == b'2' && (w[2] == b'F' || w[2] == b'f'))
+}
@@ -0,0 +1,30 @@
+mod owned_params;
+mod params;
+
+pub use self::owned_params::OwnedQueryParams;
+pub use self::params::QueryParams;
+
+use crate::percent;
+
+use std::borrow::Cow;
+
+/// Decodes a component of a query, where `+` is a space.
+///
+/// Invalid UTF-8 is replaced with `U+FFFD`.
+fn decode_component(s: &str) -> Cow<'_, str> {
+    if s.contains('+') {
+        let s = s.replace('+', " ");
+        return Cow::Owned(into_string(percent::decode(&s, false)).into_owned());
+    }
+    into_string(percent::decode(s, false))
+}
+
+fn into_string(bytes: Cow<'_, [u8]>) -> Cow<'_, str> {
+    match bytes {
+        Cow::Borrowed(b) => String::from_utf8_lossy(b),
+        Cow::Owned(v) => match String::from_utf8(v) {
+            Ok(s) => Cow::Owned(s),
+            Err(e) => Cow::Owned(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        },
+    }
+}
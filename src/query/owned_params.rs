@@ -0,0 +1,71 @@
+use super::QueryParams;
+
+use std::str::FromStr;
+
+/// The owned version of [`QueryParams`].
+///
+/// `RouterService` stores the parameters of each request in its extensions.
+#[derive(Debug, Default, Clone)]
+pub struct OwnedQueryParams {
+    pairs: Vec<(Box<str>, Box<str>)>,
+}
+
+impl OwnedQueryParams {
+    pub fn new(params: &QueryParams<'_>) -> Self {
+        let pairs = params.iter().map(|(k, v)| (k.into(), v.into())).collect();
+        Self { pairs }
+    }
+
+    /// Parses the query of the request URI.
+    #[cfg(feature = "http-router")]
+    pub fn from_request<B>(req: &http::Request<B>) -> Self {
+        Self::new(&QueryParams::from_request(req))
+    }
+
+    /// Returns the first value of `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter().find(|&(k, _)| k == name).map(|(_, v)| v)
+    }
+
+    /// Returns all values of `name` in order.
+    pub fn get_all<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.iter()
+            .filter_map(move |(k, v)| if k == name { Some(v) } else { None })
+    }
+
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.get(name).map(T::from_str)
+    }
+
+    /// Deserializes the parameters into `T`. See [`QueryParams::deserialize`].
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, T: serde::Deserialize<'de>>(
+        &'de self,
+    ) -> Result<T, crate::DeserializeError> {
+        crate::router::deserialize_params(self.iter())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.pairs.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl IntoIterator for OwnedQueryParams {
+    type Item = (String, String);
+    type IntoIter = std::iter::Map<
+        std::vec::IntoIter<(Box<str>, Box<str>)>,
+        fn((Box<str>, Box<str>)) -> (String, String),
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter().map(|(k, v)| (k.into(), v.into()))
+    }
+}
@@ -0,0 +1,72 @@
+use super::decode_component;
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use smallvec::SmallVec;
+
+/// The percent-decoded parameters of a query string, such as `a=1&b=x+y&a=2`.
+///
+/// A key can be repeated, and a key without `=` has an empty value.
+#[derive(Debug)]
+pub struct QueryParams<'a> {
+    pairs: SmallVec<[(Cow<'a, str>, Cow<'a, str>); 8]>,
+}
+
+impl<'a> QueryParams<'a> {
+    pub fn new(query: &'a str) -> Self {
+        let pairs = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                (decode_component(k), decode_component(v))
+            })
+            .collect();
+        Self { pairs }
+    }
+
+    /// Parses the query of the request URI.
+    #[cfg(feature = "http-router")]
+    pub fn from_request<B>(req: &'a http::Request<B>) -> Self {
+        Self::new(req.uri().query().unwrap_or(""))
+    }
+
+    /// Returns the first value of `name`.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter().find(|&(k, _)| k == name).map(|(_, v)| v)
+    }
+
+    /// Returns all values of `name` in order.
+    pub fn get_all<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.iter()
+            .filter_map(move |(k, v)| if k == name { Some(v) } else { None })
+    }
+
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
+        self.get(name).map(T::from_str)
+    }
+
+    /// Deserializes the parameters into `T`.
+    ///
+    /// Structs and maps are filled by names, where a sequence field takes all values of a repeated name
+    /// and any other field takes the first value.
+    #[cfg(feature = "serde")]
+    pub fn deserialize<'de, T: serde::Deserialize<'de>>(
+        &'de self,
+    ) -> Result<T, crate::DeserializeError> {
+        crate::router::deserialize_params(self.iter())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.pairs.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
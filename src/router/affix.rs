@@ -0,0 +1,135 @@
+use smallvec::SmallVec;
+
+/// A segment mixing literals and captures, such as `:name.:ext` or `v:version`.
+///
+/// A capture matches at least one byte and stops at the first occurrence of the following literal.
+#[derive(Debug, Clone)]
+pub(super) struct Affix {
+    tokens: Vec<Token>,
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Token {
+    Literal(Box<str>),
+    Capture(Box<str>),
+}
+
+#[inline]
+pub(crate) fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl Affix {
+    pub(super) fn parse(part: &str) -> Result<Self, &'static str> {
+        let mut tokens = Vec::new();
+        let mut rest = part;
+        while !rest.is_empty() {
+            match rest.strip_prefix(':') {
+                Some(s) => {
+                    let end = s.find(|c| !is_name_char(c)).unwrap_or(s.len());
+                    if end == 0 {
+                        return Err("capture name can not be empty");
+                    }
+                    if let Some(Token::Capture(_)) = tokens.last() {
+                        return Err("captures in a segment must be separated by literals");
+                    }
+                    // `:user-id` was a capture named `user-id`, so it is not read as `:user` and `-id`
+                    let mut tail = s[end..].chars();
+                    let dash = tail.next() == Some('-');
+                    if dash && matches!(tail.next(), Some(c) if is_name_char(c)) {
+                        return Err("capture name can only contain ASCII letters, digits and '_'");
+                    }
+                    tokens.push(Token::Capture(s[..end].into()));
+                    rest = &s[end..];
+                }
+                None => {
+                    let end = rest.find(':').unwrap_or(rest.len());
+                    tokens.push(Token::Literal(rest[..end].into()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        Ok(Self { tokens })
+    }
+
+    pub(super) fn literal_len(&self) -> usize {
+        self.tokens
+            .iter()
+            .map(|t| match t {
+                Token::Literal(lit) => lit.len(),
+                Token::Capture(_) => 0,
+            })
+            .sum()
+    }
+
+    pub(super) fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub(super) fn is_match(&self, segment: &str) -> bool {
+        self.match_with(segment, |_, _| {})
+    }
+
+    pub(super) fn extract<'p, 's: 'p>(
+        &'s self,
+        segment: &'p str,
+        buf: &mut SmallVec<[(&'p str, &'p str); 8]>,
+    ) {
+        self.match_with(segment, |name, value| buf.push((name, value)));
+    }
+
+    fn match_with<'p, 's: 'p>(
+        &'s self,
+        segment: &'p str,
+        mut f: impl FnMut(&'s str, &'p str),
+    ) -> bool {
+        let mut pos = 0;
+        let mut iter = self.tokens.iter().peekable();
+        while let Some(token) = iter.next() {
+            let rest = &segment[pos..];
+            match token {
+                Token::Literal(lit) => {
+                    if !rest.starts_with(&**lit) {
+                        return false;
+                    }
+                    pos += lit.len();
+                }
+                Token::Capture(name) => {
+                    let end = match iter.peek() {
+                        Some(Token::Literal(lit)) => {
+                            // a capture matches at least one char
+                            let skip = rest.chars().next().map_or(0, char::len_utf8);
+                            match rest[skip..].find(&**lit) {
+                                Some(i) => i + skip,
+                                None => return false,
+                            }
+                        }
+                        _ => rest.len(),
+                    };
+                    if end == 0 {
+                        return false;
+                    }
+                    f(name, &rest[..end]);
+                    pos += end;
+                }
+            }
+        }
+        pos == segment.len()
+    }
+}
+
+/// Two affixes are equal if they have the same literals at the same places.
+impl PartialEq for Affix {
+    fn eq(&self, other: &Self) -> bool {
+        self.tokens.len() == other.tokens.len()
+            && self
+                .tokens
+                .iter()
+                .zip(other.tokens.iter())
+                .all(|pair| match pair {
+                    (Token::Literal(lhs), Token::Literal(rhs)) => lhs == rhs,
+                    (Token::Capture(_), Token::Capture(_)) => true,
+                    _ => false,
+                })
+    }
+}
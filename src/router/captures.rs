@@ -1,15 +1,23 @@
+use std::borrow::Cow;
 use std::fmt::{self, Debug};
 use std::ops::Deref;
-use std::str::FromStr;
+use std::str::{FromStr, Utf8Error};
+
+use super::PercentDecoding;
 
 use smallvec::SmallVec;
 
+#[derive(Clone)]
 pub struct Captures<'a> {
     path: &'a str,
+    host: Option<&'a str>,
     buf: SmallVec<[(&'a str, &'a str); 8]>,
+    canonical: Option<Cow<'a, str>>,
+    redirect: bool,
+    decoding: PercentDecoding,
 }
 
-impl Captures<'_> {
+impl<'a> Captures<'a> {
     pub fn get(&self, name: &str) -> Option<&str> {
         self.buf
             .iter()
@@ -19,12 +27,31 @@ impl Captures<'_> {
     pub fn parse<T: FromStr>(&self, name: &str) -> Option<Result<T, T::Err>> {
         self.get(name).map(T::from_str)
     }
+
+    /// Returns the percent-decoded value of a capture if the router decodes paths,
+    /// or the original value otherwise.
+    pub fn get_decoded(&self, name: &str) -> Option<Result<Cow<'a, str>, Utf8Error>> {
+        let &(_, v) = self.buf.iter().find(|&&(k, _)| name == k)?;
+        Some(self.decoding.decode(v))
+    }
+
+    /// Returns the path in the form declared by the matched route if it differs from the requested path.
+    ///
+    /// The static segments taken from the route are percent-encoded.
+    pub fn canonical_path(&self) -> Option<&str> {
+        self.canonical.as_deref()
+    }
+
+    /// Returns the canonical path if the request should be redirected to it.
+    pub fn redirect(&self) -> Option<&str> {
+        self.canonical.as_deref().filter(|_| self.redirect)
+    }
 }
 
 impl<'a> Deref for Captures<'a> {
     type Target = [(&'a str, &'a str)];
     fn deref(&self) -> &Self::Target {
-        &*self.buf
+        &self.buf
     }
 }
 
@@ -32,7 +59,11 @@ impl<'a> Captures<'a> {
     pub(super) fn new(path: &'a str) -> Self {
         Self {
             path,
+            host: None,
             buf: SmallVec::new(),
+            canonical: None,
+            redirect: false,
+            decoding: PercentDecoding::Disabled,
         }
     }
 
@@ -41,17 +72,46 @@ impl<'a> Captures<'a> {
         &mut self.buf
     }
 
+    pub(super) fn set_canonical(&mut self, path: Cow<'a, str>, redirect: bool) {
+        self.canonical = Some(path);
+        self.redirect = redirect;
+    }
+
+    pub(super) fn set_decoding(&mut self, decoding: PercentDecoding) {
+        self.decoding = decoding;
+    }
+
+    #[inline(always)]
+    pub(super) fn decoding(&self) -> PercentDecoding {
+        self.decoding
+    }
+
     #[inline(always)]
     pub(super) fn path(&self) -> &'a str {
         self.path
     }
+
+    #[inline(always)]
+    pub(super) fn host(&self) -> Option<&'a str> {
+        self.host
+    }
+
+    /// Puts the captures of a host pattern before the captures of the path.
+    #[cfg(feature = "http-router")]
+    pub(crate) fn set_host(&mut self, host: &'a str, captures: &[(&'a str, &'a str)]) {
+        self.host = Some(host);
+        self.buf.insert_many(0, captures.iter().cloned());
+    }
 }
 
 impl Debug for Captures<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Captures")
             .field("path", &self.path)
+            .field("host", &self.host)
             .field("buf", &self.buf.as_slice())
+            .field("canonical", &self.canonical)
+            .field("redirect", &self.redirect)
             .finish()
     }
 }
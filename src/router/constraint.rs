@@ -0,0 +1,43 @@
+use super::param_type::ParamType;
+
+use regex::Regex;
+
+/// A predicate on the value of a capture segment.
+#[derive(Debug, Clone)]
+pub(super) enum Constraint {
+    Regex(Regex),
+    Type(ParamType),
+}
+
+impl Constraint {
+    pub(super) fn regex(src: &str) -> Result<Self, &'static str> {
+        match Regex::new(&format!("^(?:{})$", src)) {
+            Ok(re) => Ok(Self::Regex(re)),
+            Err(_) => Err("invalid regex in capture constraint"),
+        }
+    }
+
+    pub(super) fn param_type(name: &str) -> Result<Self, &'static str> {
+        match ParamType::from_name(name) {
+            Some(ty) => Ok(Self::Type(ty)),
+            None => Err("unknown capture type"),
+        }
+    }
+
+    pub(super) fn is_match(&self, value: &str) -> bool {
+        match *self {
+            Self::Regex(ref re) => re.is_match(value),
+            Self::Type(ty) => ty.is_valid(value),
+        }
+    }
+}
+
+impl PartialEq for Constraint {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Regex(lhs), Self::Regex(rhs)) => lhs.as_str() == rhs.as_str(),
+            (Self::Type(lhs), Self::Type(rhs)) => lhs == rhs,
+            _ => false,
+        }
+    }
+}
@@ -1,157 +1,291 @@
 #![allow(unsafe_code)]
 
+use super::captures::Captures;
 use super::endpoint::Endpoint;
-use super::{Bits, Route, Router, Segment};
-
-use crate::bitset::{BitStorage, FixedBitSet};
+use super::normalize::{normalize, resolve};
+use super::optional;
+use super::pattern::{
+    calc_rank, collect_affixes, collect_constraints, Part, Pattern, COLON, SLASH, STAR,
+};
+use super::{Bits, CaseSensitivity, Constraint, Route, Router, Segment};
+use super::{EncodedSlash, PercentDecoding, TrailingSlash};
+
+use crate::bitset::SmallBitSet;
+use crate::percent;
 use crate::strmap::StrMap;
 
+use std::borrow::Cow;
 use std::ptr::NonNull;
 
 use smallvec::SmallVec;
 
-type SmallKvBuffer<'a> = SmallVec<[(&'a str, &'a str); 8]>;
+pub(super) type SmallKvBuffer<'a> = SmallVec<[(&'a str, &'a str); 8]>;
 
-const STAR: char = '*';
-const COLON: char = ':';
-const SLASH: char = '/';
+/// Parts of a path and the static segments which they matched case-insensitively.
+pub(super) type Fixes<'a> = SmallVec<[(&'a str, &'a str); 4]>;
 
 impl<T> Router<T> {
-    fn check_parts(parts: &[&str]) -> Result<(), &'static str> {
-        for &part in parts {
-            if part.starts_with(STAR) {
-                return Err("wildcard pattern can only appear at end");
-            }
-            if part.starts_with(COLON) && part.len() == 1 {
-                return Err("capture name can not be empty");
-            }
-        }
-        Ok(())
-    }
-
     fn extend_segments(segments: &mut Vec<Segment>, num: usize) {
         let (wildcard_base, num_base) = match segments.last() {
             Some(s) => (s.wildcard.clone(), s.num_mask.clone()),
-            None => (FixedBitSet::zero(), FixedBitSet::zero()),
+            None => (SmallBitSet::zero(), SmallBitSet::zero()),
         };
 
         segments.resize_with(num, || Segment {
             static_map: StrMap::new(),
+            folded_map: StrMap::new(),
             dynamic: wildcard_base.clone(),
+            constraints: Vec::new(),
+            affixes: Vec::new(),
             wildcard: wildcard_base.clone(),
             num_mask: num_base.clone(),
         });
     }
 
-    pub(super) fn insert_endpoint(
-        &mut self,
-        pattern: &str,
-        endpoint: Endpoint<T>,
-    ) -> Result<(), &'static str> {
-        if !pattern.starts_with(SLASH) {
-            return Err("pattern must start with '/'");
+    pub(super) fn set_case(&mut self, case: CaseSensitivity) -> Result<(), &'static str> {
+        let old = self.case;
+        self.apply_case(case);
+        if case != CaseSensitivity::Sensitive && self.has_collision() {
+            self.apply_case(old);
+            return Err("pattern collision occured");
         }
-        // safety: pattern.len() >= 1
-        let pattern = unsafe { pattern.get_unchecked(1..) };
+        Ok(())
+    }
 
-        if self.routes.len() >= Bits::bit_size() {
-            return Err("a single router can not hold more than 128 routes");
+    fn apply_case(&mut self, case: CaseSensitivity) {
+        self.case = case;
+        for s in self.segments.iter_mut() {
+            s.folded_map = StrMap::new();
+            if case == CaseSensitivity::Sensitive {
+                continue;
+            }
+            for (key, m) in s.static_map.iter() {
+                s.folded_map
+                    .find_mut_with(&case.fold(key), SmallBitSet::zero)
+                    .union_with(m);
+            }
         }
-
-        let mut parts: SmallVec<[&str; 8]> = pattern.split(SLASH).collect();
-
-        if parts.len() > 64 {
-            return Err("a single router can not hold more than 64 segments");
+        for e in self.endpoints.iter_mut() {
+            if let Endpoint::Router(r) = e {
+                r.apply_case(case);
+            }
         }
+    }
 
-        let nested = endpoint.is_router();
+    /// Returns whether two routes, including the ones of nested routers, match the same paths.
+    fn has_collision(&self) -> bool {
+        let collides =
+            self.routes
+                .iter()
+                .enumerate()
+                .any(|(i, route)| match Pattern::parse(&route.pattern) {
+                    Ok(p) => self.collides(&p.parts, p.wildcard.is_some(), route.nested, Some(i)),
+                    Err(_) => false,
+                });
+        collides
+            || self.endpoints.iter().any(|e| match e {
+                Endpoint::Router(r) => r.has_collision(),
+                _ => false,
+            })
+    }
 
-        let wildcard: Option<Box<str>> = {
-            // safety: parts.len() >= 1
-            let last = unsafe { parts.get_unchecked(parts.len() - 1) };
+    pub(super) fn set_decoding(&mut self, decoding: PercentDecoding) {
+        self.decoding = decoding;
+        for e in self.endpoints.iter_mut() {
+            if let Endpoint::Router(r) = e {
+                r.set_decoding(decoding);
+            }
+        }
+    }
 
-            if last.starts_with(STAR) {
-                // safety: last.len() >= 1
-                let last: Box<str> = unsafe { last.get_unchecked(1..) }.into();
-                if last.is_empty() {
-                    return Err("capture name can not be empty");
+    /// Returns the routes which may share a path with `parts`.
+    fn overlap_mask(&self, parts: &[Part<'_>]) -> SmallBitSet<Bits> {
+        let mut enable_mask: SmallBitSet<Bits> = SmallBitSet::one(self.routes.len());
+        for (part, s) in parts.iter().zip(self.segments.iter()) {
+            let mut e = s.dynamic.clone();
+            match *part {
+                Part::Static(part) if self.case != CaseSensitivity::Sensitive => {
+                    if let Some(m) = s.folded_map.find(&self.case.fold(part)) {
+                        e.union_with(m);
+                    }
+                    for (a, m) in s.affixes.iter() {
+                        if a.is_match(part) {
+                            e.union_with(m);
+                        }
+                    }
                 }
-                if nested {
-                    return Err("wildcard pattern can not be used for router prefix");
+                Part::Static(part) => {
+                    if let Some(m) = s.static_map.find(part) {
+                        e.union_with(m);
+                    }
+                    for (a, m) in s.affixes.iter() {
+                        if a.is_match(part) {
+                            e.union_with(m);
+                        }
+                    }
+                }
+                Part::Capture(..) => {}
+                Part::Affix(ref a) => {
+                    if let Some((_, m)) = s.affixes.iter().find(|(k, _)| k == a) {
+                        e.union_with(m);
+                    }
                 }
-                parts.pop();
-                Some(last)
-            } else {
-                None
             }
-        };
+            enable_mask.intersect_with(&e);
+        }
+        enable_mask
+    }
 
-        Self::check_parts(&parts)?;
+    /// Returns whether a route other than `except` matches exactly the same paths as the pattern.
+    fn collides(
+        &self,
+        parts: &[Part<'_>],
+        wildcard: bool,
+        nested: bool,
+        except: Option<usize>,
+    ) -> bool {
+        if self.routes.is_empty() {
+            return false;
+        }
+        let segment_num = parts.len() + usize::from(nested | wildcard);
+        let rank = calc_rank(parts);
+        let constraints = collect_constraints(parts);
+        let affixes = collect_affixes(parts);
 
-        let segment_num = parts.len() + usize::from(nested | wildcard.is_some());
+        let enable_mask = self.overlap_mask(parts);
+        // the mask is not intersected when parts is empty, so the index may be out of bound
+        let mut iter = enable_mask
+            .iter_ones()
+            .filter(|&i| Some(i) != except)
+            .filter_map(|i| self.routes.get(i));
 
-        let mut captures: Vec<(Box<str>, usize)> = Vec::new();
-        let mut rank: u64 = 0;
-
-        for (i, &part) in parts.iter().enumerate() {
-            rank <<= 1;
-            if part.starts_with(COLON) {
-                // safety: part.len() >= 1
-                let name: Box<str> = unsafe { part.get_unchecked(1..) }.into();
-                captures.push((name, i));
-            } else {
-                rank |= 1;
+        iter.any(|route: &Route| -> bool {
+            if route.nested {
+                return nested || segment_num >= route.segment_num;
             }
-        }
+            if nested {
+                return route.segment_num >= segment_num;
+            }
+            let same = route.wildcard.is_some() == wildcard;
+            same && rank == route.rank
+                && constraints == route.constraints
+                && affixes == route.affixes
+        })
+    }
 
-        let check_collision = || {
-            if self.routes.is_empty() {
-                return false;
+    pub(super) fn insert_endpoint(
+        &mut self,
+        pattern: &str,
+        name: Option<&str>,
+        mut endpoint: Endpoint<T>,
+    ) -> Result<(), &'static str> {
+        if let Some(name) = name {
+            if self.has_name(name) {
+                return Err("route name already exists");
             }
-            let mut enable_mask: FixedBitSet<Bits> = FixedBitSet::one();
-            for (part, s) in parts.iter().cloned().zip(self.segments.iter()) {
-                let mut e = s.dynamic.clone();
-                if !part.starts_with(COLON) {
-                    if let Some(m) = s.static_map.find(part) {
-                        e.union_with(m);
-                    }
-                }
-                enable_mask.intersect_with(&e);
+        }
+        if let Endpoint::Router(ref r) = endpoint {
+            let mut names = Vec::new();
+            r.collect_names(&mut names);
+            if names.into_iter().any(|n| self.has_name(n)) {
+                return Err("route name already exists");
             }
-            // safety: FixedBitSet<u128>.iter_ones(), i in 0..128, and routes.len() < 128
-            let mut iter = enable_mask
-                .iter_ones()
-                .map(|i| unsafe { self.routes.get_unchecked(i) });
+        }
 
-            iter.any(|route: &Route| -> bool {
-                if route.nested {
-                    return nested || segment_num >= route.segment_num;
-                }
-                if nested {
-                    return route.segment_num >= segment_num;
+        let patterns = optional::expand(pattern)?;
+
+        if let Endpoint::Router(ref mut r) = endpoint {
+            r.set_case(self.case)?;
+            r.set_decoding(self.decoding);
+        }
+        let nested = endpoint.is_router();
+        let endpoint_id = self.endpoints.len();
+        let route_num = self.routes.len();
+        self.endpoints.push(endpoint);
+        self.sources.push(pattern.into());
+
+        for pattern in patterns.iter() {
+            if let Err(e) = self.insert_route(pattern, name, nested, endpoint_id) {
+                while self.routes.len() > route_num {
+                    self.remove_route(self.routes.len() - 1);
                 }
-                let same = !(route.wildcard.is_some() ^ wildcard.is_some());
-                same && rank == route.rank
-            })
-        };
+                self.endpoints.truncate(endpoint_id);
+                self.sources.truncate(endpoint_id);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
 
-        if check_collision() {
+    fn insert_route(
+        &mut self,
+        pattern: &str,
+        name: Option<&str>,
+        nested: bool,
+        endpoint: usize,
+    ) -> Result<(), &'static str> {
+        let full_pattern = pattern;
+        let Pattern { parts, wildcard } = Pattern::parse(pattern)?;
+
+        if nested && wildcard.is_some() {
+            return Err("wildcard pattern can not be used for router prefix");
+        }
+
+        let segment_num = parts.len() + usize::from(nested | wildcard.is_some());
+
+        let mut captures: Vec<(Box<str>, usize)> = Vec::new();
+        for (i, part) in parts.iter().enumerate() {
+            if let Part::Capture(name, _) = *part {
+                captures.push((name.into(), i));
+            }
+        }
+        if self.collides(&parts, wildcard.is_some(), nested, None) {
             return Err("pattern collision occured");
         }
 
+        let rank = calc_rank(&parts);
+        let constraints = collect_constraints(&parts);
+        let affixes = collect_affixes(&parts);
+
         if segment_num > self.segments.len() {
             Self::extend_segments(&mut self.segments, segment_num);
         }
 
         let id = self.routes.len();
-
-        for (part, s) in parts.iter().cloned().zip(self.segments.iter_mut()) {
-            if part.starts_with(COLON) {
-                s.dynamic.set(id, true)
-            } else {
-                s.static_map
-                    .find_mut_with(part, FixedBitSet::zero)
-                    .set(id, true)
+        let case = self.case;
+
+        for (part, s) in parts.iter().zip(self.segments.iter_mut()) {
+            match *part {
+                Part::Static(part) => {
+                    s.static_map
+                        .find_mut_with(part, SmallBitSet::zero)
+                        .set(id, true);
+                    if case != CaseSensitivity::Sensitive {
+                        s.folded_map
+                            .find_mut_with(&case.fold(part), SmallBitSet::zero)
+                            .set(id, true);
+                    }
+                }
+                Part::Capture(_, None) => s.dynamic.set(id, true),
+                Part::Capture(_, Some(ref c)) => {
+                    s.dynamic.set(id, true);
+                    match s.constraints.iter_mut().find(|(k, _)| k == c) {
+                        Some((_, m)) => m.set(id, true),
+                        None => {
+                            let mut m = SmallBitSet::zero();
+                            m.set(id, true);
+                            s.constraints.push((c.clone(), m));
+                        }
+                    }
+                }
+                Part::Affix(ref a) => match s.affixes.iter_mut().find(|(k, _)| k == a) {
+                    Some((_, m)) => m.set(id, true),
+                    None => {
+                        let mut m = SmallBitSet::zero();
+                        m.set(id, true);
+                        s.affixes.push((a.clone(), m));
+                    }
+                },
             }
         }
 
@@ -173,12 +307,16 @@ impl<T> Router<T> {
             }
         }
 
-        self.endpoints.push(endpoint);
         self.routes.push(Route {
+            pattern: full_pattern.into(),
+            endpoint,
+            name: name.map(Into::into),
             segment_num,
             rank,
             captures,
-            wildcard,
+            constraints,
+            affixes,
+            wildcard: wildcard.map(Into::into),
             nested,
         });
 
@@ -187,24 +325,316 @@ impl<T> Router<T> {
 }
 
 impl<T> Router<T> {
-    pub(super) fn real_find<'p, 's: 'p>(
-        &'s self,
-        path: &'p str,
-        captures: &mut SmallKvBuffer<'p>,
-    ) -> Option<&'s T> {
-        let parts: SmallVec<[&str; 8]> = trim_first_slash(path).split(SLASH).collect();
-        self.find_with_parts(path, &parts, captures)
-            .map(|p| unsafe { &*p.as_ptr() })
+    /// Returns the data of the route declared by `pattern`,
+    /// also when it is nested or its captures are named differently.
+    #[cfg(feature = "http-router")]
+    pub(crate) fn data_mut_by_pattern(&mut self, pattern: &str) -> Option<&mut T> {
+        let expansions = optional::expand(pattern).ok()?;
+        let patterns: SmallVec<[Pattern<'_>; 4]> = expansions
+            .iter()
+            .filter_map(|p| Pattern::parse(p).ok())
+            .collect();
+
+        // like `remove_data`, the pattern has to declare all the routes sharing the data
+        let first = patterns.first()?;
+        let target = self.locate(&first.parts, first.wildcard.is_some())? as *const T;
+        let count = patterns
+            .iter()
+            .filter(|p| {
+                self.locate(&p.parts, p.wildcard.is_some())
+                    .map(|t| t as *const T)
+                    == Some(target)
+            })
+            .count();
+        if count != patterns.len() || count != self.route_count(target) {
+            return None;
+        }
+        self.locate_mut(&first.parts, first.wildcard.is_some())
+    }
+
+    /// Calls `f` with the data of every endpoint, including the nested ones.
+    #[cfg(feature = "hyper-service")]
+    pub(crate) fn for_each_data(&self, f: &mut dyn FnMut(&T)) {
+        for endpoint in self.endpoints.iter() {
+            match endpoint {
+                Endpoint::Data(t) => f(t),
+                Endpoint::Router(r) => r.for_each_data(f),
+            }
+        }
+    }
+
+    /// Calls `f` with the data of every endpoint, including the nested ones.
+    #[cfg(feature = "http-router")]
+    pub(crate) fn for_each_data_mut(&mut self, f: &mut dyn FnMut(&mut T)) {
+        for endpoint in self.endpoints.iter_mut() {
+            match endpoint {
+                Endpoint::Data(t) => f(t),
+                Endpoint::Router(r) => r.for_each_data_mut(f),
+            }
+        }
+    }
+
+    pub(super) fn remove_data(&mut self, pattern: &str) -> Option<T> {
+        let expansions = optional::expand(pattern).ok()?;
+        let patterns: SmallVec<[Pattern<'_>; 4]> = expansions
+            .iter()
+            .filter_map(|p| Pattern::parse(p).ok())
+            .collect();
+
+        // the data is shared by all the expansions of its pattern, so none of them is removed alone
+        let targets: SmallVec<[*const T; 4]> = patterns
+            .iter()
+            .filter_map(|p| self.locate(&p.parts, p.wildcard.is_some()))
+            .map(|t| t as *const T)
+            .collect();
+        for &t in targets.iter() {
+            if targets.iter().filter(|&&u| u == t).count() != self.route_count(t) {
+                return None;
+            }
+        }
+
+        let mut ans = None;
+        for p in patterns.iter() {
+            if let Some(data) = self.remove_with_parts(&p.parts, p.wildcard.is_some()) {
+                ans = Some(data);
+            }
+        }
+        ans
+    }
+
+    /// Returns the id of the route inserted with `parts`,
+    /// and the position of the parts of its nested router if it is nested.
+    fn route_of(&self, parts: &[Part<'_>], wildcard: bool) -> Option<(usize, Option<usize>)> {
+        if self.routes.is_empty() {
+            return None;
+        }
+
+        let segment_num = parts.len() + usize::from(wildcard);
+
+        let candidates = self.overlap_mask(parts);
+        let mut iter = candidates.iter_ones().filter(|&i| i < self.routes.len());
+
+        iter.find_map(|id| {
+            let route = &self.routes[id];
+            if route.nested {
+                let pos = route.segment_num - 1;
+                if parts.len() <= pos
+                    || calc_rank(&parts[..pos]) != route.rank
+                    || collect_constraints(&parts[..pos]) != route.constraints
+                    || collect_affixes(&parts[..pos]) != route.affixes
+                {
+                    return None;
+                }
+                match self.endpoints[route.endpoint] {
+                    Endpoint::Router(ref r) => r.route_of(&parts[pos..], wildcard)?,
+                    Endpoint::Data(_) => return None,
+                };
+                Some((id, Some(pos)))
+            } else if route.segment_num == segment_num
+                && route.wildcard.is_some() == wildcard
+                && calc_rank(parts) == route.rank
+                && collect_constraints(parts) == route.constraints
+                && collect_affixes(parts) == route.affixes
+            {
+                Some((id, None))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn locate(&self, parts: &[Part<'_>], wildcard: bool) -> Option<&T> {
+        let (id, pos) = self.route_of(parts, wildcard)?;
+        match (&self.endpoints[self.routes[id].endpoint], pos) {
+            (Endpoint::Router(r), Some(pos)) => r.locate(&parts[pos..], wildcard),
+            (Endpoint::Data(t), None) => Some(t),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "http-router")]
+    fn locate_mut(&mut self, parts: &[Part<'_>], wildcard: bool) -> Option<&mut T> {
+        let (id, pos) = self.route_of(parts, wildcard)?;
+        match (&mut self.endpoints[self.routes[id].endpoint], pos) {
+            (Endpoint::Router(r), Some(pos)) => r.locate_mut(&parts[pos..], wildcard),
+            (Endpoint::Data(t), None) => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of routes, including the nested ones, which lead to `target`.
+    fn route_count(&self, target: *const T) -> usize {
+        let count = |r: &Route| match self.endpoints[r.endpoint] {
+            Endpoint::Router(ref n) => n.route_count(target),
+            Endpoint::Data(ref t) => usize::from(std::ptr::eq(t, target)),
+        };
+        self.routes.iter().map(count).sum()
+    }
+
+    fn remove_with_parts(&mut self, parts: &[Part<'_>], wildcard: bool) -> Option<T> {
+        let (id, pos) = self.route_of(parts, wildcard)?;
+        let e = self.routes[id].endpoint;
+        let pos = match pos {
+            Some(pos) => pos,
+            None => {
+                return match self.remove_route(id) {
+                    Some(Endpoint::Data(t)) => Some(t),
+                    _ => None,
+                }
+            }
+        };
+        let data = match self.endpoints[e] {
+            Endpoint::Router(ref mut r) => r.remove_with_parts(&parts[pos..], wildcard)?,
+            Endpoint::Data(_) => return None,
+        };
+        if let Endpoint::Router(ref r) = self.endpoints[e] {
+            if r.routes.is_empty() {
+                // the endpoint is removed along with the last route sharing it
+                while let Some(id) = self.routes.iter().position(|r| r.endpoint == e) {
+                    if self.remove_route(id).is_some() {
+                        break;
+                    }
+                }
+            }
+        }
+        Some(data)
+    }
+
+    /// Removes a route and returns its endpoint if no other route shares it.
+    fn remove_route(&mut self, id: usize) -> Option<Endpoint<T>> {
+        for s in self.segments.iter_mut() {
+            s.static_map.retain(|m| {
+                m.remove(id);
+                !m.is_zero()
+            });
+            s.folded_map.retain(|m| {
+                m.remove(id);
+                !m.is_zero()
+            });
+            s.constraints.retain_mut(|(_, m)| {
+                m.remove(id);
+                !m.is_zero()
+            });
+            s.affixes.retain_mut(|(_, m)| {
+                m.remove(id);
+                !m.is_zero()
+            });
+            s.dynamic.remove(id);
+            s.wildcard.remove(id);
+            s.num_mask.remove(id);
+        }
+
+        let e = self.routes.remove(id).endpoint;
+
+        let segment_num = self.routes.iter().map(|r| r.segment_num).max();
+        self.segments.truncate(segment_num.unwrap_or(0));
+
+        if self.routes.iter().any(|r| r.endpoint == e) {
+            return None;
+        }
+        for r in self.routes.iter_mut() {
+            if r.endpoint > e {
+                r.endpoint -= 1;
+            }
+        }
+        self.sources.remove(e);
+        Some(self.endpoints.remove(e))
+    }
+}
+
+impl<T> Router<T> {
+    pub(super) fn real_find<'p, 's: 'p>(&'s self, captures: &mut Captures<'p>) -> Option<&'s T> {
+        self.search(captures).map(|p| unsafe { &*p.as_ptr() })
     }
 
     pub(super) fn real_find_mut<'p, 's: 'p>(
-        &'s self,
-        path: &'p str,
-        captures: &mut SmallKvBuffer<'p>,
+        &'s mut self,
+        captures: &mut Captures<'p>,
     ) -> Option<&'s mut T> {
-        let parts: SmallVec<[&str; 8]> = trim_first_slash(path).split(SLASH).collect();
-        self.find_with_parts(path, &parts, captures)
-            .map(|p| unsafe { &mut *p.as_ptr() })
+        self.search(captures).map(|p| unsafe { &mut *p.as_ptr() })
+    }
+
+    fn search<'p, 's: 'p>(&'s self, captures: &mut Captures<'p>) -> Option<NonNull<T>> {
+        let path = captures.path();
+        if self.decoding == PercentDecoding::Enabled(EncodedSlash::Reject)
+            && percent::has_encoded_slash(path)
+        {
+            return None;
+        }
+        if !self.normalization {
+            return self.search_normalized(captures);
+        }
+        let normalized = match normalize(path)? {
+            Cow::Borrowed(_) => return self.search_normalized(captures),
+            Cow::Owned(normalized) => normalized,
+        };
+
+        // the captures of the normalized path can not outlive it, so they are taken from the path,
+        // and a wildcard spanning segments which are apart in the path does not match
+        let mut found = Captures::new(&normalized);
+        let p = self.search_normalized(&mut found)?;
+        let (segments, _) = resolve(&path[1..])?;
+        for &(name, value) in found.iter() {
+            let value = denormalize(path, &normalized, &segments, value)?;
+            // safety: the names of captures are borrowed from the routes of self
+            let name: &'s str = unsafe { &*(name as *const str) };
+            captures.buffer().push((name, value));
+        }
+        captures.set_decoding(self.decoding);
+        let canonical = found.canonical_path().unwrap_or(&normalized).to_owned();
+        captures.set_canonical(Cow::Owned(canonical), true);
+        Some(p)
+    }
+
+    fn search_normalized<'p, 's: 'p>(&'s self, captures: &mut Captures<'p>) -> Option<NonNull<T>> {
+        let path = captures.path();
+        captures.set_decoding(self.decoding);
+
+        let mut parts: SmallVec<[&str; 8]> = trim_first_slash(path).split(SLASH).collect();
+        let mut fixes = Fixes::new();
+
+        // whether a trailing slash is added or removed
+        let mut slash = None;
+        let p = match self.find_with_parts(path, &parts, captures.buffer(), &mut fixes) {
+            Some(p) => p,
+            None => {
+                if self.trailing_slash == TrailingSlash::Strict || path.len() <= 1 {
+                    return None;
+                }
+                let add = if parts.last() == Some(&"") {
+                    parts.pop();
+                    false
+                } else {
+                    // an empty str at the end of path, so that offsets are still valid
+                    parts.push(&path[path.len()..]);
+                    true
+                };
+                captures.buffer().clear();
+                fixes.clear();
+                let p = self.find_with_parts(path, &parts, captures.buffer(), &mut fixes)?;
+                slash = Some(add);
+                p
+            }
+        };
+
+        if fixes.is_empty() && slash.is_none() {
+            return Some(p);
+        }
+        let canonical = match (apply_fixes(path, &fixes), slash) {
+            (mut s, Some(true)) => {
+                s.to_mut().push(SLASH);
+                s
+            }
+            (Cow::Borrowed(s), Some(false)) => Cow::Borrowed(&s[..s.len() - 1]),
+            (Cow::Owned(mut s), Some(false)) => {
+                s.pop();
+                Cow::Owned(s)
+            }
+            (s, None) => s,
+        };
+        let redirect = slash.is_some() && self.trailing_slash == TrailingSlash::Redirect;
+        captures.set_canonical(canonical, redirect);
+        Some(p)
     }
 
     fn find_with_parts<'p, 's: 'p>(
@@ -212,18 +642,71 @@ impl<T> Router<T> {
         path: &'p str,
         parts: &[&'p str],
         captures: &mut SmallKvBuffer<'p>,
+        fixes: &mut Fixes<'p>,
     ) -> Option<NonNull<T>> {
+        let enable_mask = self.candidates(parts);
+
+        let base_ptr = self.routes.as_ptr();
+        let mut iter = enable_mask
+            .iter_ones()
+            .map(|i| unsafe { &*base_ptr.add(i) });
+
+        let route: &Route = {
+            let mut ans = iter.next()?;
+            for r in iter {
+                if r.segment_num == ans.segment_num && r.rank > ans.rank {
+                    ans = r;
+                }
+                if r.segment_num > ans.segment_num {
+                    ans = r;
+                }
+            }
+            ans
+        };
+
+        self.extract(route, path, parts, captures, fixes);
+
+        let endpoint = unsafe { self.endpoints.get_unchecked(route.endpoint) };
+        match endpoint {
+            Endpoint::Data(t) => Some(NonNull::from(t)),
+            Endpoint::Router(r) => {
+                let (path, parts) = nested_parts(route, path, parts);
+                r.find_with_parts(path, parts, captures, fixes)
+            }
+        }
+    }
+
+    /// Returns the routes matching `parts`, regardless of the routes in nested routers.
+    pub(super) fn candidates(&self, parts: &[&str]) -> SmallBitSet<Bits> {
         if self.routes.is_empty() {
-            return None;
+            return SmallBitSet::zero();
         }
 
-        let mut enable_mask: FixedBitSet<Bits> = FixedBitSet::one();
+        let mut enable_mask: SmallBitSet<Bits> = SmallBitSet::one(self.routes.len());
 
         for (part, s) in parts.iter().cloned().zip(self.segments.iter()) {
             let mut e = s.dynamic.clone();
-            if let Some(m) = s.static_map.find(part) {
+            for (c, m) in s.constraints.iter() {
+                if !self.match_constraint(c, part) {
+                    e.difference_with(m);
+                }
+            }
+            let decoded = self.decoding.decode_bytes(part);
+            if let Some(m) = s.static_map.find_bytes(&decoded) {
                 e.union_with(m);
             }
+            if self.case != CaseSensitivity::Sensitive {
+                if let Ok(part) = std::str::from_utf8(&decoded) {
+                    if let Some(m) = s.folded_map.find(&self.case.fold(part)) {
+                        e.union_with(m);
+                    }
+                }
+            }
+            for (a, m) in s.affixes.iter() {
+                if a.is_match(part) {
+                    e.union_with(m);
+                }
+            }
             enable_mask.intersect_with(&e);
         }
         if parts.len() > self.segments.len() {
@@ -240,52 +723,74 @@ impl<T> Router<T> {
             let s = unsafe { self.segments.get_unchecked(parts.len() - 1) };
             enable_mask.intersect_with(&s.num_mask);
         }
+        enable_mask
+    }
 
-        let base_ptr = self.routes.as_ptr();
-        let mut iter = enable_mask
-            .iter_ones()
-            .map(|i| unsafe { &*base_ptr.add(i) });
+    /// Returns true if the static segment `key` matches `part`.
+    pub(super) fn match_static(&self, key: &str, part: &str) -> bool {
+        let decoded = self.decoding.decode_bytes(part);
+        if key.as_bytes() == &*decoded {
+            return true;
+        }
+        match std::str::from_utf8(&decoded) {
+            Ok(part) if self.case != CaseSensitivity::Sensitive => {
+                self.case.fold(key) == self.case.fold(part)
+            }
+            _ => false,
+        }
+    }
 
-        let route: &Route = {
-            let mut ans = iter.next()?;
-            for r in iter {
-                if r.segment_num == ans.segment_num && r.rank > ans.rank {
-                    ans = r;
+    /// Returns true if the value of `part` satisfies the constraint `c`.
+    pub(super) fn match_constraint(&self, c: &Constraint, part: &str) -> bool {
+        match self.decoding.decode(part) {
+            Ok(value) => c.is_match(&value),
+            Err(_) => false,
+        }
+    }
+
+    /// Pushes the captures of a matched route, and the static segments matched case-insensitively.
+    pub(super) fn extract<'p, 's: 'p>(
+        &'s self,
+        route: &'s Route,
+        path: &'p str,
+        parts: &[&'p str],
+        captures: &mut SmallKvBuffer<'p>,
+        fixes: &mut Fixes<'p>,
+    ) {
+        if self.case != CaseSensitivity::Sensitive {
+            let keys = trim_first_slash(&route.pattern).split(SLASH);
+            for (key, &part) in keys.zip(parts.iter()) {
+                if key.contains(COLON) || key.starts_with(STAR) {
+                    continue;
                 }
-                if r.segment_num > ans.segment_num {
-                    ans = r;
+                if key.as_bytes() != &*self.decoding.decode_bytes(part) {
+                    fixes.push((part, key));
                 }
             }
-            ans
-        };
+        }
 
+        let mut affixes = route.affixes.iter().peekable();
         for &(ref name, i) in route.captures.iter() {
+            while let Some((j, a)) = affixes.next_if(|&&(j, _)| j < i) {
+                a.extract(parts[*j], captures);
+            }
             // safety: i < route.segment_num <= parts.len()
             captures.push((&**name, unsafe { parts.get_unchecked(i) }));
         }
+        for (j, a) in affixes {
+            a.extract(parts[*j], captures);
+        }
         if let Some(ref name) = route.wildcard {
             // safety: parts and path point to the same str, and path is the base ptr
             let offset =
                 (calc_offset(path, parts[route.segment_num - 1]) as usize).saturating_sub(1);
             captures.push((&**name, unsafe { path.get_unchecked(offset..) }));
         }
-
-        let idx = unsafe { offset_from(route, self.routes.as_ptr()) };
-        let endpoint = unsafe { self.endpoints.get_unchecked(idx) };
-        match endpoint {
-            Endpoint::Data(t) => Some(NonNull::from(t)),
-            Endpoint::Router(r) => {
-                let parts = unsafe { parts.get_unchecked((route.segment_num - 1)..) };
-                let offset = (calc_offset(path, parts[0]) as usize).saturating_sub(1);
-                let path = unsafe { path.get_unchecked(offset..) };
-                r.find_with_parts(path, parts, captures)
-            }
-        }
     }
 }
 
 #[inline(always)]
-fn trim_first_slash(s: &str) -> &str {
+pub(super) fn trim_first_slash(s: &str) -> &str {
     if s.starts_with(SLASH) {
         unsafe { s.get_unchecked(1..) }
     } else {
@@ -293,16 +798,64 @@ fn trim_first_slash(s: &str) -> &str {
     }
 }
 
+/// Returns the path and parts left for the nested router of a matched route.
+pub(super) fn nested_parts<'p, 'a>(
+    route: &Route,
+    path: &'p str,
+    parts: &'a [&'p str],
+) -> (&'p str, &'a [&'p str]) {
+    let parts = &parts[(route.segment_num - 1)..];
+    // safety: parts and path point to the same str, and path is the base ptr
+    let offset = (calc_offset(path, parts[0]) as usize).saturating_sub(1);
+    (unsafe { path.get_unchecked(offset..) }, parts)
+}
+
+/// Returns the slice of `path` equal to `value`, which is a slice of the normalized form of `path`.
+///
+/// `segments` are the segments of `path` left by normalization.
+fn denormalize<'p>(
+    path: &'p str,
+    normalized: &str,
+    segments: &[&'p str],
+    value: &str,
+) -> Option<&'p str> {
+    let offset = calc_offset(normalized, value);
+    // the distance between the segment containing the value in both paths,
+    // where each segment is preceded by a slash
+    let mut delta = 0;
+    let mut start = 1;
+    for seg in segments {
+        if start - 1 > offset {
+            break;
+        }
+        delta = calc_offset(path, seg) - start;
+        start += seg.len() as isize + 1;
+    }
+    let offset = (offset + delta) as usize;
+    path.get(offset..offset + value.len())
+        .filter(|&v| v == value)
+}
+
+/// Replaces the parts of path with the encoded static segments they matched.
+fn apply_fixes<'p>(path: &'p str, fixes: &[(&str, &str)]) -> Cow<'p, str> {
+    if fixes.is_empty() {
+        return Cow::Borrowed(path);
+    }
+    let mut buf = String::with_capacity(path.len());
+    let mut pos = 0;
+    for &(part, key) in fixes {
+        let start = calc_offset(path, part) as usize;
+        buf.push_str(&path[pos..start]);
+        percent::encode_segment(key, &mut buf);
+        pos = start + part.len();
+    }
+    buf.push_str(&path[pos..]);
+    Cow::Owned(buf)
+}
+
 #[inline(always)]
 fn calc_offset(src: &str, dst: &str) -> isize {
     let p2 = dst.as_ptr() as isize;
     let p1 = src.as_ptr() as isize;
     p2 - p1
 }
-
-// safety: caller should ensure that size_of::<T>() != 0 and ptr >= src
-#[inline(always)]
-unsafe fn offset_from<T>(ptr: *const T, src: *const T) -> usize {
-    assert!(std::mem::size_of::<T>() != 0);
-    (ptr as usize).wrapping_sub(src as usize) / std::mem::size_of::<T>()
-}
@@ -0,0 +1,452 @@
+use super::{Captures, OwnedCaptures, PercentDecoding};
+
+use std::borrow::Cow;
+use std::fmt::{self, Display};
+
+use serde::de::value::{BorrowedStrDeserializer, CowStrDeserializer};
+use serde::de::{self, DeserializeSeed, Visitor};
+use serde::forward_to_deserialize_any;
+use smallvec::SmallVec;
+
+/// An error occurred when deserializing captures.
+#[derive(Debug)]
+pub struct DeserializeError {
+    param: Option<Box<str>>,
+    msg: Box<str>,
+}
+
+impl DeserializeError {
+    /// Returns the name of the offending parameter if any.
+    pub fn param(&self) -> Option<&str> {
+        self.param.as_deref()
+    }
+
+    fn with_param(mut self, param: &str) -> Self {
+        if self.param.is_none() {
+            self.param = Some(param.into());
+        }
+        self
+    }
+}
+
+impl Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.param {
+            Some(ref param) => write!(f, "invalid parameter `{}`: {}", param, self.msg),
+            None => f.write_str(&self.msg),
+        }
+    }
+}
+
+impl std::error::Error for DeserializeError {}
+
+impl de::Error for DeserializeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            param: None,
+            msg: msg.to_string().into(),
+        }
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        Self::custom("missing parameter").with_param(field)
+    }
+}
+
+type Params<'de> = SmallVec<[(&'de str, Cow<'de, str>); 8]>;
+
+/// The values of a parameter, which are more than one if the name is repeated.
+type Values<'de> = SmallVec<[Cow<'de, str>; 1]>;
+
+/// Deserializes a list of parameters into `T`. See [`Captures::deserialize`].
+pub(crate) fn deserialize_params<'de, T: de::Deserialize<'de>>(
+    params: impl IntoIterator<Item = (&'de str, &'de str)>,
+) -> Result<T, DeserializeError> {
+    let params = params.into_iter().map(|(n, v)| (n, Cow::Borrowed(v)));
+    T::deserialize(ParamsDeserializer(params.collect()))
+}
+
+/// Decodes the values of captures before deserializing them.
+fn deserialize_decoded<'de, T: de::Deserialize<'de>>(
+    captures: impl IntoIterator<Item = (&'de str, &'de str)>,
+    decoding: PercentDecoding,
+) -> Result<T, DeserializeError> {
+    let mut params = Params::new();
+    for (name, value) in captures {
+        match decoding.decode(value) {
+            Ok(value) => params.push((name, value)),
+            Err(e) => return Err(<DeserializeError as de::Error>::custom(e).with_param(name)),
+        }
+    }
+    T::deserialize(ParamsDeserializer(params))
+}
+
+impl<'a> Captures<'a> {
+    /// Deserializes the captures into `T`.
+    ///
+    /// Structs and maps are filled by names, and tuples and sequences by positions.
+    /// A primitive value can be deserialized from the only capture.
+    /// The values are percent-decoded if the router decodes paths.
+    pub fn deserialize<T: de::Deserialize<'a>>(&self) -> Result<T, DeserializeError> {
+        deserialize_decoded(self.iter().cloned(), self.decoding())
+    }
+}
+
+impl OwnedCaptures {
+    /// Deserializes the captures into `T`. See [`Captures::deserialize`].
+    pub fn deserialize<'de, T: de::Deserialize<'de>>(&'de self) -> Result<T, DeserializeError> {
+        deserialize_decoded(self.iter(), self.decoding())
+    }
+}
+
+struct ParamsDeserializer<'de>(Params<'de>);
+
+impl<'de> ParamsDeserializer<'de> {
+    fn single(mut self) -> Result<(&'de str, Cow<'de, str>), DeserializeError> {
+        match self.0.len() {
+            1 => Ok(self.0.remove(0)),
+            n => Err(de::Error::custom(format_args!(
+                "expected 1 parameter, found {}",
+                n
+            ))),
+        }
+    }
+}
+
+macro_rules! forward_to_single {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            let (name, value) = self.single()?;
+            ValueDeserializer(value)
+                .$method(visitor)
+                .map_err(|e| e.with_param(name))
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ParamsDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        let mut groups: SmallVec<[(&'de str, Values<'de>); 8]> = SmallVec::new();
+        for (name, value) in self.0 {
+            match groups.iter_mut().find(|(n, _)| *n == name) {
+                Some((_, values)) => values.push(value),
+                None => groups.push((name, smallvec::smallvec![value])),
+            }
+        }
+        visitor.visit_map(MapAccess {
+            iter: groups.into_iter(),
+            value: None,
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(SeqAccess {
+            iter: self.0.into_iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.0.len() != len {
+            return Err(de::Error::invalid_length(self.0.len(), &visitor));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (param, value) = self.single()?;
+        ValueDeserializer(value)
+            .deserialize_enum(name, variants, visitor)
+            .map_err(|e| e.with_param(param))
+    }
+
+    forward_to_single! {
+        deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_option deserialize_identifier deserialize_ignored_any
+    }
+}
+
+struct MapAccess<'de> {
+    iter: smallvec::IntoIter<[(&'de str, Values<'de>); 8]>,
+    value: Option<(&'de str, Values<'de>)>,
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((name, values)) => {
+                self.value = Some((name, values));
+                let key = BorrowedStrDeserializer::<'de, DeserializeError>::new(name);
+                seed.deserialize(key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (name, values) = match self.value.take() {
+            Some(pair) => pair,
+            None => return Err(de::Error::custom("value is missing")),
+        };
+        seed.deserialize(ValuesDeserializer(values))
+            .map_err(|e| e.with_param(name))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct SeqAccess<'de> {
+    iter: smallvec::IntoIter<[(&'de str, Cow<'de, str>); 8]>,
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((name, value)) => seed
+                .deserialize(ValueDeserializer(value))
+                .map(Some)
+                .map_err(|e| e.with_param(name)),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializes the values of a parameter.
+///
+/// Sequences take all of the values, and other types take the first one.
+struct ValuesDeserializer<'de>(Values<'de>);
+
+impl<'de> ValuesDeserializer<'de> {
+    fn first(mut self) -> ValueDeserializer<'de> {
+        ValueDeserializer(self.0.swap_remove(0))
+    }
+}
+
+macro_rules! forward_to_first {
+    ($($method:ident)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            self.first().$method(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ValuesDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_seq(ValueSeqAccess {
+            iter: self.0.into_iter(),
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if self.0.len() != len {
+            return Err(de::Error::invalid_length(self.0.len(), &visitor));
+        }
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.first().deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_first! {
+        deserialize_any deserialize_bool
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_str deserialize_string deserialize_bytes deserialize_byte_buf
+        deserialize_unit deserialize_identifier deserialize_ignored_any
+    }
+
+    forward_to_deserialize_any! {
+        unit_struct tuple_struct map struct
+    }
+}
+
+struct ValueSeqAccess<'de> {
+    iter: smallvec::IntoIter<[Cow<'de, str>; 1]>,
+}
+
+impl<'de> de::SeqAccess<'de> for ValueSeqAccess<'de> {
+    type Error = DeserializeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.iter.next() {
+            Some(value) => seed.deserialize(ValueDeserializer(value)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+/// Deserializes a value by parsing it.
+struct ValueDeserializer<'de>(Cow<'de, str>);
+
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+            match self.0.parse() {
+                Ok(v) => visitor.$visit(v),
+                Err(e) => Err(de::Error::custom(e)),
+            }
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = DeserializeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0 {
+            Cow::Borrowed(v) => visitor.visit_borrowed_str(v),
+            Cow::Owned(v) => visitor.visit_string(v),
+        }
+    }
+
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let value = CowStrDeserializer::<DeserializeError>::new(self.0);
+        visitor.visit_enum(value)
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
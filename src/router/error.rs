@@ -5,7 +5,12 @@ pub struct RouterError {
 }
 
 impl RouterError {
-    pub(super) fn new(msg: &'static str) -> Self {
+    pub(crate) fn new(msg: &'static str) -> Self {
         Self { msg }
     }
+
+    #[cfg(all(feature = "http-router", feature = "serde"))]
+    pub(crate) fn msg(&self) -> &'static str {
+        self.msg
+    }
 }
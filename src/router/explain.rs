@@ -0,0 +1,155 @@
+use super::core::{nested_parts, Fixes, SmallKvBuffer};
+use super::endpoint::Endpoint;
+use super::pattern::{Part, Pattern};
+use super::{Route, Router};
+
+use std::fmt::{self, Display};
+
+use smallvec::SmallVec;
+
+/// The reason why a route does not match a path.
+///
+/// Segment indexes and counts refer to the whole path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    /// The path has a different number of segments.
+    SegmentCount { expected: usize, actual: usize },
+    /// The route ends with a wildcard or a nested router, which needs at least `min` segments.
+    Wildcard { min: usize, actual: usize },
+    /// The segment differs from the static segment of the route.
+    Static { index: usize },
+    /// The segment does not satisfy the constraint of the capture.
+    Constraint { index: usize },
+    /// The segment does not have the literals of the route.
+    Affix { index: usize },
+}
+
+impl Rejection {
+    fn shift(self, offset: usize) -> Self {
+        match self {
+            Self::SegmentCount { expected, actual } => Self::SegmentCount {
+                expected: expected + offset,
+                actual: actual + offset,
+            },
+            Self::Wildcard { min, actual } => Self::Wildcard {
+                min: min + offset,
+                actual: actual + offset,
+            },
+            Self::Static { index } => Self::Static {
+                index: index + offset,
+            },
+            Self::Constraint { index } => Self::Constraint {
+                index: index + offset,
+            },
+            Self::Affix { index } => Self::Affix {
+                index: index + offset,
+            },
+        }
+    }
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::SegmentCount { expected, actual } => {
+                write!(f, "expected {} segments, found {}", expected, actual)
+            }
+            Self::Wildcard { min, actual } => {
+                write!(f, "expected at least {} segments, found {}", min, actual)
+            }
+            Self::Static { index } => write!(f, "static segment {} does not match", index),
+            Self::Constraint { index } => {
+                write!(f, "segment {} does not satisfy the constraint", index)
+            }
+            Self::Affix { index } => write!(f, "segment {} does not match the literals", index),
+        }
+    }
+}
+
+impl<T> Router<T> {
+    pub(super) fn find_all_with_parts<'p, 's: 'p>(
+        &'s self,
+        path: &'p str,
+        parts: &[&'p str],
+        prefix: &SmallKvBuffer<'p>,
+        ans: &mut Vec<(&'s T, SmallKvBuffer<'p>)>,
+    ) {
+        let mut routes: SmallVec<[&Route; 8]> = self
+            .candidates(parts)
+            .iter_ones()
+            .filter_map(|i| self.routes.get(i))
+            .collect();
+        // the same order as `find`, since the sort is stable
+        routes.sort_by(|lhs, rhs| {
+            (rhs.segment_num.cmp(&lhs.segment_num)).then_with(|| rhs.rank.cmp(&lhs.rank))
+        });
+
+        for route in routes {
+            let mut captures = prefix.clone();
+            self.extract(route, path, parts, &mut captures, &mut Fixes::new());
+            match self.endpoints[route.endpoint] {
+                Endpoint::Data(ref t) => ans.push((t, captures)),
+                Endpoint::Router(ref r) => {
+                    let (path, parts) = nested_parts(route, path, parts);
+                    r.find_all_with_parts(path, parts, &captures, ans);
+                }
+            }
+        }
+    }
+
+    pub(super) fn explain_with_parts(
+        &self,
+        prefix: &str,
+        parts: &[&str],
+        offset: usize,
+        ans: &mut Vec<(String, Rejection)>,
+    ) {
+        for route in self.routes.iter() {
+            let pattern = format!("{}{}", prefix, route.pattern);
+            let reason = self.check(route, parts).map(|r| r.shift(offset));
+            match (&self.endpoints[route.endpoint], reason) {
+                (Endpoint::Data(_), None) => {}
+                (Endpoint::Data(_), Some(reason)) => ans.push((pattern, reason)),
+                (Endpoint::Router(r), None) => {
+                    let pos = route.segment_num - 1;
+                    r.explain_with_parts(&pattern, &parts[pos..], offset + pos, ans);
+                }
+                (Endpoint::Router(r), Some(reason)) => {
+                    for (p, _) in r.routes() {
+                        ans.push((format!("{}{}", pattern, p), reason));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the first reason why `route` does not match `parts`.
+    fn check(&self, route: &Route, parts: &[&str]) -> Option<Rejection> {
+        let actual = parts.len();
+        if route.nested || route.wildcard.is_some() {
+            if actual < route.segment_num {
+                let min = route.segment_num;
+                return Some(Rejection::Wildcard { min, actual });
+            }
+        } else if actual != route.segment_num {
+            let expected = route.segment_num;
+            return Some(Rejection::SegmentCount { expected, actual });
+        }
+
+        // the pattern has been parsed when the route is inserted
+        let pattern = Pattern::parse(&route.pattern).ok()?;
+        for (index, (p, &part)) in pattern.parts.iter().zip(parts.iter()).enumerate() {
+            match *p {
+                Part::Static(key) if !self.match_static(key, part) => {
+                    return Some(Rejection::Static { index })
+                }
+                Part::Capture(_, Some(ref c)) if !self.match_constraint(c, part) => {
+                    return Some(Rejection::Constraint { index })
+                }
+                Part::Affix(ref a) if !a.is_match(part) => return Some(Rejection::Affix { index }),
+                _ => {}
+            }
+        }
+        None
+    }
+}
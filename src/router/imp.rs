@@ -1,6 +1,15 @@
 use super::captures::Captures;
+use super::core::trim_first_slash;
 use super::error::RouterError;
-use super::Router;
+use super::normalize::normalize;
+use super::pattern::SLASH;
+use super::TrailingSlash;
+use super::{CaseSensitivity, EncodedSlash, ParamType, PercentDecoding, Rejection, Router};
+use crate::percent;
+
+use std::borrow::Cow;
+
+use smallvec::SmallVec;
 
 impl<T> Router<T> {
     pub fn new() -> Self {
@@ -8,6 +17,83 @@ impl<T> Router<T> {
             segments: Vec::new(),
             routes: Vec::new(),
             endpoints: Vec::new(),
+            sources: Vec::new(),
+            trailing_slash: TrailingSlash::Strict,
+            case: CaseSensitivity::Sensitive,
+            decoding: PercentDecoding::Disabled,
+            normalization: false,
+        }
+    }
+
+    pub fn trailing_slash(&self) -> TrailingSlash {
+        self.trailing_slash
+    }
+
+    pub fn set_trailing_slash(&mut self, policy: TrailingSlash) -> &mut Self {
+        self.trailing_slash = policy;
+        self
+    }
+
+    pub fn case_sensitivity(&self) -> CaseSensitivity {
+        self.case
+    }
+
+    /// Sets how static segments are compared, including the ones of nested routers.
+    ///
+    /// # Panics
+    /// Panics if two routes match the same paths in the new mode, such as `/api` and `/API`.
+    pub fn set_case_sensitivity(&mut self, case: CaseSensitivity) -> &mut Self {
+        if let Err(e) = self.set_case(case) {
+            panic!("{}: case = {:?}", e, case);
+        }
+        self
+    }
+
+    /// Sets how static segments are compared, and keeps the mode if two routes would match the same paths.
+    pub fn try_set_case_sensitivity(
+        &mut self,
+        case: CaseSensitivity,
+    ) -> Result<&mut Self, RouterError> {
+        match self.set_case(case) {
+            Ok(()) => Ok(self),
+            Err(msg) => Err(RouterError::new(msg)),
+        }
+    }
+
+    pub fn percent_decoding(&self) -> PercentDecoding {
+        self.decoding
+    }
+
+    /// Sets whether paths are percent-decoded, including the ones of nested routers.
+    pub fn set_percent_decoding(&mut self, decoding: PercentDecoding) -> &mut Self {
+        self.set_decoding(decoding);
+        self
+    }
+
+    pub fn path_normalization(&self) -> bool {
+        self.normalization
+    }
+
+    /// Enables collapsing empty segments and resolving dot segments before matching.
+    ///
+    /// A path which is changed by normalization matches the routes of its normalized form,
+    /// and [`Captures::redirect`] returns the normalized path.
+    /// It does not match if a wildcard would capture segments which are apart in the path,
+    /// and the path to redirect to is returned by [`Router::normalize_path`].
+    /// `%2e` is resolved as a dot whatever the percent-decoding mode.
+    pub fn set_path_normalization(&mut self, enabled: bool) -> &mut Self {
+        self.normalization = enabled;
+        self
+    }
+
+    /// Returns the normalized path if normalization is enabled, or the path itself otherwise.
+    ///
+    /// Returns `None` if the path escapes above the root.
+    pub fn normalize_path<'p>(&self, path: &'p str) -> Option<Cow<'p, str>> {
+        if self.normalization {
+            normalize(path)
+        } else {
+            Some(Cow::Borrowed(path))
         }
     }
 
@@ -15,36 +101,116 @@ impl<T> Router<T> {
         self.segments.clear();
         self.routes.clear();
         self.endpoints.clear();
+        self.sources.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Removes the routes declared by `pattern`.
+    ///
+    /// The routes expanded from an optional pattern are only removed together,
+    /// so `/posts` does not remove `/posts/:page?`.
+    pub fn remove(&mut self, pattern: &str) -> Option<T> {
+        self.remove_data(pattern)
+    }
+
+    /// Returns the patterns and data of all routes in insertion order.
+    ///
+    /// The patterns of nested routes include the prefixes of their routers.
+    pub fn routes(&self) -> impl Iterator<Item = (String, &T)> + '_ {
+        self.route_entries()
+            .into_iter()
+            .map(|(pattern, _, data)| (pattern, data))
     }
 
     pub fn find<'p, 's: 'p>(&'s self, path: &'p str) -> Option<(&'s T, Captures<'p>)> {
         let mut captures = Captures::new(path);
-        let data = self.real_find(path, &mut captures.buffer())?;
+        let data = self.real_find(&mut captures)?;
         Some((data, captures))
     }
 
+    /// Returns the data and captures of all routes matching `path` in priority order.
+    ///
+    /// The trailing slash policy and path normalization are not applied.
+    pub fn find_all<'p, 's: 'p>(&'s self, path: &'p str) -> Vec<(&'s T, Captures<'p>)> {
+        if self.decoding == PercentDecoding::Enabled(EncodedSlash::Reject)
+            && percent::has_encoded_slash(path)
+        {
+            return Vec::new();
+        }
+        let parts: SmallVec<[&str; 8]> = trim_first_slash(path).split(SLASH).collect();
+        let mut ans = Vec::new();
+        self.find_all_with_parts(path, &parts, &SmallVec::new(), &mut ans);
+        ans.into_iter()
+            .map(|(t, buf)| {
+                let mut captures = Captures::new(path);
+                *captures.buffer() = buf;
+                captures.set_decoding(self.decoding);
+                (t, captures)
+            })
+            .collect()
+    }
+
+    /// Returns the routes which do not match `path`, and the reasons.
+    pub fn explain(&self, path: &str) -> Vec<(String, Rejection)> {
+        let parts: SmallVec<[&str; 8]> = trim_first_slash(path).split(SLASH).collect();
+        let mut ans = Vec::new();
+        self.explain_with_parts("", &parts, 0, &mut ans);
+        ans
+    }
+
     pub fn find_mut<'p, 's: 'p>(&'s mut self, path: &'p str) -> Option<(&'s mut T, Captures<'p>)> {
         let mut captures = Captures::new(path);
-        let data = self.real_find_mut(path, &mut captures.buffer())?;
+        let data = self.real_find_mut(&mut captures)?;
         Some((data, captures))
     }
 
     pub fn insert(&mut self, pattern: &str, data: T) -> &mut Self {
-        if let Err(e) = self.insert_endpoint(pattern, data.into()) {
+        if let Err(e) = self.insert_endpoint(pattern, None, data.into()) {
             panic!("{}: pattern = {:?}", e, pattern);
         }
         self
     }
 
     pub fn try_insert(&mut self, pattern: &str, data: T) -> Result<&mut Self, RouterError> {
-        match self.insert_endpoint(pattern, data.into()) {
+        match self.insert_endpoint(pattern, None, data.into()) {
             Ok(()) => Ok(self),
             Err(msg) => Err(RouterError::new(msg)),
         }
     }
 
+    pub fn insert_named(&mut self, name: &str, pattern: &str, data: T) -> &mut Self {
+        if let Err(e) = self.insert_endpoint(pattern, Some(name), data.into()) {
+            panic!("{}: pattern = {:?}, name = {:?}", e, pattern, name);
+        }
+        self
+    }
+
+    pub fn try_insert_named(
+        &mut self,
+        name: &str,
+        pattern: &str,
+        data: T,
+    ) -> Result<&mut Self, RouterError> {
+        match self.insert_endpoint(pattern, Some(name), data.into()) {
+            Ok(()) => Ok(self),
+            Err(msg) => Err(RouterError::new(msg)),
+        }
+    }
+
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, RouterError> {
+        self.build_url(name, params).map_err(RouterError::new)
+    }
+
+    /// Returns the captures of a named route and their declared types.
+    pub fn params(&self, name: &str) -> Option<Vec<(&str, Option<ParamType>)>> {
+        self.route_params(name)
+    }
+
     pub fn insert_router(&mut self, prefix: &str, router: Router<T>) -> &mut Self {
-        if let Err(e) = self.insert_endpoint(prefix, router.into()) {
+        if let Err(e) = self.insert_endpoint(prefix, None, router.into()) {
             panic!("{}: pattern = {:?}", e, prefix);
         }
         self
@@ -55,7 +221,7 @@ impl<T> Router<T> {
         prefix: &str,
         router: Router<T>,
     ) -> Result<&mut Self, RouterError> {
-        match self.insert_endpoint(prefix, router.into()) {
+        match self.insert_endpoint(prefix, None, router.into()) {
             Ok(()) => Ok(self),
             Err(msg) => Err(RouterError::new(msg)),
         }
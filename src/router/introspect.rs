@@ -0,0 +1,30 @@
+use super::endpoint::Endpoint;
+use super::Router;
+
+impl<T> Router<T> {
+    /// Returns the full patterns, names and data of all routes in insertion order.
+    pub(crate) fn route_entries(&self) -> Vec<(String, Option<&str>, &T)> {
+        let mut routes = Vec::new();
+        self.collect_routes("", &mut routes);
+        routes
+    }
+
+    fn collect_routes<'s>(
+        &'s self,
+        prefix: &str,
+        routes: &mut Vec<(String, Option<&'s str>, &'s T)>,
+    ) {
+        for (e, endpoint) in self.endpoints.iter().enumerate() {
+            let name = self
+                .routes
+                .iter()
+                .find(|r| r.endpoint == e)
+                .and_then(|r| r.name.as_deref());
+            let full = format!("{}{}", prefix, self.sources[e]);
+            match endpoint {
+                Endpoint::Data(t) => routes.push((full, name, t)),
+                Endpoint::Router(r) => r.collect_routes(&full, routes),
+            }
+        }
+    }
+}
@@ -1,16 +1,44 @@
+mod affix;
 mod captures;
+mod constraint;
 mod core;
+#[cfg(feature = "serde")]
+mod de;
 mod endpoint;
 mod error;
+mod explain;
 mod imp;
+mod introspect;
+mod normalize;
+mod optional;
 mod owned_captures;
+mod param_type;
+mod pattern;
+mod policy;
+mod rank;
+mod reverse;
 
+#[cfg(feature = "http-router")]
+pub(crate) use self::affix::is_name_char;
 pub use self::captures::Captures;
+#[cfg(feature = "serde")]
+pub use self::de::DeserializeError;
+
+#[cfg(feature = "serde")]
+pub(crate) use self::de::deserialize_params;
 pub use self::error::RouterError;
+pub use self::explain::Rejection;
+#[cfg(feature = "http-router")]
+pub(crate) use self::normalize::normalize;
 pub use self::owned_captures::OwnedCaptures;
+pub use self::param_type::ParamType;
+pub use self::policy::{CaseSensitivity, EncodedSlash, PercentDecoding, TrailingSlash};
 
+use self::affix::Affix;
+use self::constraint::Constraint;
 use self::endpoint::Endpoint;
-use crate::bitset::FixedBitSet;
+use self::rank::Rank;
+use crate::bitset::SmallBitSet;
 use crate::strmap::StrMap;
 
 #[derive(Debug, Default)]
@@ -18,23 +46,37 @@ pub struct Router<T> {
     segments: Vec<Segment>,
     routes: Vec<Route>,
     endpoints: Vec<Endpoint<T>>,
+    /// The pattern of each endpoint as written.
+    sources: Vec<Box<str>>,
+    trailing_slash: TrailingSlash,
+    case: CaseSensitivity,
+    decoding: PercentDecoding,
+    normalization: bool,
 }
 
 type Bits = u128;
 
 #[derive(Debug)]
 struct Segment {
-    static_map: StrMap<FixedBitSet<Bits>>,
-    dynamic: FixedBitSet<Bits>,
-    wildcard: FixedBitSet<Bits>,
-    num_mask: FixedBitSet<Bits>,
+    static_map: StrMap<SmallBitSet<Bits>>,
+    folded_map: StrMap<SmallBitSet<Bits>>,
+    dynamic: SmallBitSet<Bits>,
+    constraints: Vec<(Constraint, SmallBitSet<Bits>)>,
+    affixes: Vec<(Affix, SmallBitSet<Bits>)>,
+    wildcard: SmallBitSet<Bits>,
+    num_mask: SmallBitSet<Bits>,
 }
 
 #[derive(Debug)]
 struct Route {
+    pattern: Box<str>,
+    endpoint: usize,
+    name: Option<Box<str>>,
     segment_num: usize,
-    rank: u64,
+    rank: Rank,
     wildcard: Option<Box<str>>,
     captures: Vec<(Box<str>, usize)>,
+    constraints: Vec<(usize, Constraint)>,
+    affixes: Vec<(usize, Affix)>,
     nested: bool,
 }
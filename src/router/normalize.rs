@@ -0,0 +1,94 @@
+use super::pattern::SLASH;
+
+use std::borrow::Cow;
+
+use smallvec::SmallVec;
+
+enum Kind {
+    Empty,
+    Dot,
+    DotDot,
+    Normal,
+}
+
+/// `%2e` is a dot whatever the percent-decoding mode (RFC 3986, section 6.2.2.2).
+fn strip_dot(s: &str) -> Option<&str> {
+    if let Some(rest) = s.strip_prefix('.') {
+        return Some(rest);
+    }
+    match s.as_bytes().get(..3) {
+        Some(b) if b.eq_ignore_ascii_case(b"%2e") => Some(&s[3..]),
+        _ => None,
+    }
+}
+
+fn kind(seg: &str) -> Kind {
+    let mut rest = seg;
+    let mut dots = 0;
+    while dots < 3 {
+        match strip_dot(rest) {
+            Some(r) => rest = r,
+            None => break,
+        }
+        dots += 1;
+    }
+    match (rest.is_empty(), dots) {
+        (true, 0) => Kind::Empty,
+        (true, 1) => Kind::Dot,
+        (true, 2) => Kind::DotDot,
+        _ => Kind::Normal,
+    }
+}
+
+/// Collapses empty segments and resolves dot segments as RFC 3986 does.
+///
+/// Returns `None` if the path escapes above the root.
+pub(crate) fn normalize(path: &str) -> Option<Cow<'_, str>> {
+    let body = match path.strip_prefix(SLASH) {
+        Some(body) => body,
+        None => return Some(Cow::Borrowed(path)),
+    };
+    let n = body.split(SLASH).count();
+    let is_normal = body
+        .split(SLASH)
+        .enumerate()
+        .all(|(i, seg)| match kind(seg) {
+            Kind::Empty => i == n - 1,
+            Kind::Normal => true,
+            Kind::Dot | Kind::DotDot => false,
+        });
+    if is_normal {
+        return Some(Cow::Borrowed(path));
+    }
+
+    let (stack, trailing) = resolve(body)?;
+    let mut buf = String::with_capacity(path.len());
+    for seg in stack.iter() {
+        buf.push(SLASH);
+        buf.push_str(seg);
+    }
+    if trailing || buf.is_empty() {
+        buf.push(SLASH);
+    }
+    Some(Cow::Owned(buf))
+}
+
+/// Returns the segments left after resolving the dot segments of `body`,
+/// and whether the normalized path ends with a slash.
+pub(super) fn resolve(body: &str) -> Option<(SmallVec<[&str; 8]>, bool)> {
+    let n = body.split(SLASH).count();
+    let mut stack: SmallVec<[&str; 8]> = SmallVec::new();
+    let mut trailing = false;
+    for (i, seg) in body.split(SLASH).enumerate() {
+        let k = kind(seg);
+        trailing = i == n - 1 && !matches!(k, Kind::Normal);
+        match k {
+            Kind::Normal => stack.push(seg),
+            Kind::DotDot => {
+                stack.pop()?;
+            }
+            Kind::Empty | Kind::Dot => {}
+        }
+    }
+    Some((stack, trailing))
+}
@@ -0,0 +1,96 @@
+/// Expands optional parts of a pattern into all of its alternatives.
+///
+/// `/archive(/:year(/:month))` becomes `/archive`, `/archive/:year` and `/archive/:year/:month`.
+/// A segment ending with `?` is a shorthand of a group, so `/posts/:page?` is `/posts(/:page)`.
+pub(super) fn expand(pattern: &str) -> Result<Vec<String>, &'static str> {
+    if !pattern.contains(['(', ')', '?']) {
+        return Ok(vec![pattern.to_owned()]);
+    }
+    let pattern = desugar(pattern)?;
+    let mut ans = expand_groups(&pattern)?;
+    // `/:lang?` leaves the root path when its only segment is absent
+    for p in ans.iter_mut().filter(|p| p.is_empty()) {
+        p.push('/');
+    }
+    Ok(ans)
+}
+
+/// Rewrites `/seg?` into `(/seg)`. Regex constraints enclosed in `<>` are left untouched.
+fn desugar(pattern: &str) -> Result<String, &'static str> {
+    let mut buf = String::with_capacity(pattern.len() + 2);
+    let mut seg_start = 0;
+    let mut in_constraint = false;
+    let mut iter = pattern.chars().peekable();
+    while let Some(c) = iter.next() {
+        match c {
+            '<' => in_constraint = true,
+            '>' => in_constraint = false,
+            '/' if !in_constraint => seg_start = buf.len(),
+            '?' if !in_constraint => {
+                if !matches!(iter.peek(), None | Some('/') | Some(')')) {
+                    return Err("'?' can only appear at the end of a segment");
+                }
+                if buf[seg_start..].contains(['(', ')']) {
+                    return Err("'?' can not be used with parentheses in the same segment");
+                }
+                buf.insert(seg_start, '(');
+                buf.push(')');
+                continue;
+            }
+            _ => {}
+        }
+        buf.push(c);
+    }
+    Ok(buf)
+}
+
+fn expand_groups(pattern: &str) -> Result<Vec<String>, &'static str> {
+    let (open, close) = match find_group(pattern)? {
+        Some(group) => group,
+        None => return Ok(vec![pattern.to_owned()]),
+    };
+    let prefix = &pattern[..open];
+    let inner = expand_groups(&pattern[open + 1..close])?;
+    let rest = expand_groups(&pattern[close + 1..])?;
+
+    let mut ans = Vec::with_capacity((inner.len() + 1) * rest.len());
+    for i in std::iter::once("").chain(inner.iter().map(|s| s.as_str())) {
+        for r in rest.iter() {
+            ans.push(format!("{}{}{}", prefix, i, r));
+        }
+    }
+    Ok(ans)
+}
+
+/// Finds the first top-level group.
+fn find_group(pattern: &str) -> Result<Option<(usize, usize)>, &'static str> {
+    let mut depth = 0;
+    let mut open = 0;
+    let mut in_constraint = false;
+    for (i, c) in pattern.char_indices() {
+        match c {
+            '<' => in_constraint = true,
+            '>' => in_constraint = false,
+            '(' if !in_constraint => {
+                if depth == 0 {
+                    open = i;
+                }
+                depth += 1;
+            }
+            ')' if !in_constraint => {
+                if depth == 0 {
+                    return Err("unbalanced parentheses in pattern");
+                }
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(Some((open, i)));
+                }
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err("unbalanced parentheses in pattern");
+    }
+    Ok(None)
+}
@@ -1,12 +1,15 @@
-use crate::router::Captures;
+use crate::router::{Captures, PercentDecoding};
+
+use std::borrow::Cow;
 
 use std::iter::{DoubleEndedIterator, ExactSizeIterator, FusedIterator};
-use std::str::FromStr;
+use std::str::{FromStr, Utf8Error};
 
 #[derive(Debug)]
 pub struct OwnedCaptures {
     path: Option<Box<str>>,
     offset: Vec<(Box<str>, usize, usize)>, // (name, start, end)
+    decoding: PercentDecoding,
 }
 
 impl OwnedCaptures {
@@ -14,20 +17,39 @@ impl OwnedCaptures {
         Self {
             path: None,
             offset: Vec::new(),
+            decoding: PercentDecoding::Disabled,
         }
     }
 
     pub fn new(caps: &Captures<'_>) -> Self {
+        // the captures of a host are stored before the path
+        let host = caps.host().unwrap_or("");
+        let (host_base, path_base) = (host.as_ptr() as usize, caps.path().as_ptr() as usize);
+        let in_host = |p: usize| p >= host_base && p < host_base + host.len();
+
         let mut offset: Vec<(Box<str>, usize, usize)> = Vec::with_capacity(caps.len());
-        let base = caps.path().as_ptr() as usize;
         offset.extend(caps.iter().map(|&(name, value)| {
             let name = name.into();
-            let start = (value.as_ptr() as usize) - base;
+            let p = value.as_ptr() as usize;
+            let start = if in_host(p) {
+                p - host_base
+            } else {
+                host.len() + (p - path_base)
+            };
             let end = start + value.len();
             (name, start, end)
         }));
-        let path = some_if(!offset.is_empty(), || caps.path().into());
-        Self { path, offset }
+        let path = some_if(!offset.is_empty(), || {
+            let mut text = String::with_capacity(host.len() + caps.path().len());
+            text.push_str(host);
+            text.push_str(caps.path());
+            text.into()
+        });
+        Self {
+            path,
+            offset,
+            decoding: caps.decoding(),
+        }
     }
 
     pub fn get(&self, name: &str) -> Option<&str> {
@@ -41,6 +63,17 @@ impl OwnedCaptures {
         self.get(name).map(T::from_str)
     }
 
+    /// Returns the percent-decoded value of a capture if the router decodes paths,
+    /// or the original value otherwise.
+    pub fn get_decoded(&self, name: &str) -> Option<Result<Cow<'_, str>, Utf8Error>> {
+        self.get(name).map(|v| self.decoding.decode(v))
+    }
+
+    #[cfg(feature = "serde")]
+    pub(super) fn decoding(&self) -> PercentDecoding {
+        self.decoding
+    }
+
     pub fn iter(&self) -> Iter<'_> {
         Iter {
             path: self.path.as_deref(),
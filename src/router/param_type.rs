@@ -0,0 +1,93 @@
+use std::fmt::{self, Display};
+
+/// The declared type of a capture, such as `u64` in `/post/:id:u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamType {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    /// A hyphenated uuid, such as `67e55044-10b1-426f-9247-bb680e5fe0c8`.
+    Uuid,
+    /// Lowercase words joined by single hyphens, such as `hello-world-2`.
+    Slug,
+}
+
+impl ParamType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::Uuid => "uuid",
+            Self::Slug => "slug",
+        }
+    }
+
+    pub fn is_valid(&self, value: &str) -> bool {
+        match self {
+            Self::I8 => value.parse::<i8>().is_ok(),
+            Self::I16 => value.parse::<i16>().is_ok(),
+            Self::I32 => value.parse::<i32>().is_ok(),
+            Self::I64 => value.parse::<i64>().is_ok(),
+            Self::U8 => value.parse::<u8>().is_ok(),
+            Self::U16 => value.parse::<u16>().is_ok(),
+            Self::U32 => value.parse::<u32>().is_ok(),
+            Self::U64 => value.parse::<u64>().is_ok(),
+            Self::Uuid => is_uuid(value),
+            Self::Slug => is_slug(value),
+        }
+    }
+
+    pub(super) fn from_name(name: &str) -> Option<Self> {
+        let ty = match name {
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "uuid" => Self::Uuid,
+            "slug" => Self::Slug,
+            _ => return None,
+        };
+        Some(ty)
+    }
+}
+
+impl Display for ParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+fn is_uuid(s: &str) -> bool {
+    let bytes = s.as_bytes();
+    if bytes.len() != 36 {
+        return false;
+    }
+    bytes.iter().enumerate().all(|(i, &b)| match i {
+        8 | 13 | 18 | 23 => b == b'-',
+        _ => b.is_ascii_hexdigit(),
+    })
+}
+
+fn is_slug(s: &str) -> bool {
+    !s.is_empty()
+        && s.split('-').all(|w| {
+            !w.is_empty()
+                && w.bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit())
+        })
+}
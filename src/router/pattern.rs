@@ -0,0 +1,138 @@
+use super::affix::{is_name_char, Affix};
+use super::constraint::Constraint;
+use super::rank::{Level, Rank};
+
+use smallvec::SmallVec;
+
+pub(super) const STAR: char = '*';
+pub(super) const COLON: char = ':';
+pub(super) const SLASH: char = '/';
+
+#[derive(Debug)]
+pub(super) enum Part<'a> {
+    Static(&'a str),
+    Capture(&'a str, Option<Constraint>),
+    Affix(Affix),
+}
+
+#[derive(Debug)]
+pub(super) struct Pattern<'a> {
+    pub(super) parts: SmallVec<[Part<'a>; 8]>,
+    pub(super) wildcard: Option<&'a str>,
+}
+
+impl<'a> Part<'a> {
+    fn parse(part: &'a str) -> Result<Self, &'static str> {
+        if part.starts_with(STAR) {
+            return Err("wildcard pattern can only appear at end");
+        }
+        let rest = match part.strip_prefix(COLON) {
+            Some(rest) => rest,
+            None if part.contains(COLON) => return Ok(Part::Affix(Affix::parse(part)?)),
+            None => return Ok(Part::Static(part)),
+        };
+
+        let pos = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        let (name, tail) = rest.split_at(pos);
+
+        let constraint = if tail.is_empty() {
+            None
+        } else if let Some(src) = tail.strip_prefix('<') {
+            match src.strip_suffix('>') {
+                Some(src) if !src.is_empty() => Some(Constraint::regex(src)?),
+                _ => return Err("capture constraint must be enclosed in '<' and '>'"),
+            }
+        } else if let Some(ty) = tail.strip_prefix(COLON) {
+            Some(Constraint::param_type(ty)?)
+        } else {
+            return Ok(Part::Affix(Affix::parse(part)?));
+        };
+
+        if name.is_empty() {
+            return Err("capture name can not be empty");
+        }
+        Ok(Part::Capture(name, constraint))
+    }
+
+    pub(super) fn level(&self) -> Level {
+        match *self {
+            Part::Static(_) => Level::Static,
+            Part::Capture(_, None) => Level::Dynamic,
+            Part::Capture(_, Some(_)) => Level::Constrained,
+            Part::Affix(_) => Level::Affix,
+        }
+    }
+}
+
+impl<'a> Pattern<'a> {
+    pub(super) fn parse(pattern: &'a str) -> Result<Self, &'static str> {
+        let pattern = match pattern.strip_prefix(SLASH) {
+            Some(p) => p,
+            None => return Err("pattern must start with '/'"),
+        };
+
+        // a pattern is split into segments at every '/', including the ones in a regex
+        let mut in_constraint = false;
+        for c in pattern.chars() {
+            match c {
+                '<' => in_constraint = true,
+                '>' => in_constraint = false,
+                '/' if in_constraint => return Err("capture constraint can not contain '/'"),
+                _ => {}
+            }
+        }
+
+        let mut split: SmallVec<[&str; 8]> = pattern.split(SLASH).collect();
+
+        let wildcard = match split.last().and_then(|last| last.strip_prefix(STAR)) {
+            Some(name) => {
+                if name.is_empty() {
+                    return Err("capture name can not be empty");
+                }
+                split.pop();
+                Some(name)
+            }
+            None => None,
+        };
+
+        let mut parts = SmallVec::with_capacity(split.len());
+        for part in split {
+            parts.push(Part::parse(part)?);
+        }
+
+        Ok(Self { parts, wildcard })
+    }
+}
+
+pub(super) fn calc_rank(parts: &[Part<'_>]) -> Rank {
+    let mut rank = Rank::new();
+    for part in parts {
+        rank.push(part.level());
+        if let Part::Affix(ref a) = *part {
+            rank.push_affix(a.literal_len());
+        }
+    }
+    rank
+}
+
+pub(super) fn collect_constraints(parts: &[Part<'_>]) -> Vec<(usize, Constraint)> {
+    parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, part)| match *part {
+            Part::Capture(_, Some(ref c)) => Some((i, c.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(super) fn collect_affixes(parts: &[Part<'_>]) -> Vec<(usize, Affix)> {
+    parts
+        .iter()
+        .enumerate()
+        .filter_map(|(i, part)| match *part {
+            Part::Affix(ref a) => Some((i, a.clone())),
+            _ => None,
+        })
+        .collect()
+}
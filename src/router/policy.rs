@@ -0,0 +1,82 @@
+use crate::percent;
+
+use std::borrow::Cow;
+use std::str::Utf8Error;
+
+/// How a router treats a trailing slash which is not declared by the matched route.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// `/user/asd/` and `/user/asd` are distinct paths.
+    #[default]
+    Strict,
+    /// A path also matches the route of the same path with or without a trailing slash.
+    Ignore,
+    /// Like `Ignore`, but the canonical path is reported by [`Captures::redirect`](crate::Captures::redirect).
+    Redirect,
+}
+
+/// How a router compares static segments with a path.
+///
+/// Captured values are always the original bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseSensitivity {
+    #[default]
+    Sensitive,
+    /// `/API/Users` matches `/api/users`.
+    AsciiInsensitive,
+    /// Like `AsciiInsensitive`, but non-ASCII letters are compared by their lowercase forms.
+    UnicodeInsensitive,
+}
+
+impl CaseSensitivity {
+    pub(super) fn fold(self, s: &str) -> Cow<'_, str> {
+        match self {
+            Self::Sensitive => Cow::Borrowed(s),
+            Self::UnicodeInsensitive if !s.is_ascii() => Cow::Owned(s.to_lowercase()),
+            _ if s.bytes().any(|b| b.is_ascii_uppercase()) => Cow::Owned(s.to_ascii_lowercase()),
+            _ => Cow::Borrowed(s),
+        }
+    }
+}
+
+/// Whether a router decodes `%XX` sequences in paths.
+///
+/// Static segments are matched against the decoded path, and [`Captures::get_decoded`](crate::Captures::get_decoded)
+/// returns decoded values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PercentDecoding {
+    #[default]
+    Disabled,
+    Enabled(EncodedSlash),
+}
+
+/// How `%2F` is handled when percent-decoding is enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncodedSlash {
+    /// `%2F` is decoded into `/` which stays in the same segment.
+    #[default]
+    Decode,
+    /// `%2F` is left encoded.
+    Keep,
+    /// A path containing `%2F` never matches.
+    Reject,
+}
+
+impl PercentDecoding {
+    pub(super) fn decode_bytes(self, s: &str) -> Cow<'_, [u8]> {
+        match self {
+            Self::Disabled => Cow::Borrowed(s.as_bytes()),
+            Self::Enabled(slash) => percent::decode(s, slash == EncodedSlash::Keep),
+        }
+    }
+
+    pub(super) fn decode(self, s: &str) -> Result<Cow<'_, str>, Utf8Error> {
+        match self.decode_bytes(s) {
+            Cow::Borrowed(_) => Ok(Cow::Borrowed(s)),
+            Cow::Owned(v) => match String::from_utf8(v) {
+                Ok(s) => Ok(Cow::Owned(s)),
+                Err(e) => Err(e.utf8_error()),
+            },
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::cmp::Ordering;
+
+use smallvec::SmallVec;
+
+/// The specificity of a single segment.
+///
+/// Routes with the same rank are ordered by insertion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Level {
+    Dynamic = 0,
+    Constrained = 1,
+    Affix = 2,
+    Static = 3,
+}
+
+/// The specificity of a route.
+///
+/// It is a big unsigned integer whose base-4 digits are the levels of the segments of a pattern,
+/// the first segment being the most significant digit.
+/// Ties are broken by the lengths of the literals of the affix segments, from the first segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Rank {
+    // little-endian words, without leading zero words
+    words: SmallVec<[u64; 1]>,
+    affixes: SmallVec<[usize; 2]>,
+}
+
+impl Rank {
+    pub(super) fn new() -> Self {
+        Self {
+            words: SmallVec::new(),
+            affixes: SmallVec::new(),
+        }
+    }
+
+    /// Appends the total length of the literals of an affix segment.
+    pub(super) fn push_affix(&mut self, literal_len: usize) {
+        self.affixes.push(literal_len);
+    }
+
+    /// Shifts the rank left by one digit and appends `level` as the least significant digit.
+    pub(super) fn push(&mut self, level: Level) {
+        let mut carry = level as u64;
+        for w in self.words.iter_mut() {
+            let next = *w >> 62;
+            *w = (*w << 2) | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            self.words.push(carry);
+        }
+    }
+}
+
+impl Ord for Rank {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.words
+            .len()
+            .cmp(&other.words.len())
+            .then_with(|| self.words.iter().rev().cmp(other.words.iter().rev()))
+            .then_with(|| self.affixes.cmp(&other.affixes))
+    }
+}
+
+impl PartialOrd for Rank {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
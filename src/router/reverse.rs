@@ -0,0 +1,188 @@
+use super::affix::{Affix, Token};
+use super::endpoint::Endpoint;
+use super::{Constraint, ParamType, Route, Router};
+
+use crate::percent;
+
+use smallvec::SmallVec;
+
+impl<T> Router<T> {
+    pub(crate) fn has_name(&self, name: &str) -> bool {
+        self.visit_name(name, &mut Vec::new(), &mut |_| true)
+    }
+
+    pub(crate) fn collect_names<'s>(&'s self, names: &mut Vec<&'s str>) {
+        for route in self.routes.iter() {
+            if let Some(ref name) = route.name {
+                names.push(name);
+            }
+            if let Endpoint::Router(ref r) = self.endpoints[route.endpoint] {
+                r.collect_names(names);
+            }
+        }
+    }
+
+    /// Calls `f` with the routes from this router to every route named `name`,
+    /// until `f` returns true.
+    fn visit_name<'s>(
+        &'s self,
+        name: &str,
+        chain: &mut Vec<&'s Route>,
+        f: &mut dyn FnMut(&[&'s Route]) -> bool,
+    ) -> bool {
+        for route in self.routes.iter() {
+            chain.push(route);
+            let done = match self.endpoints[route.endpoint] {
+                Endpoint::Router(ref r) => r.visit_name(name, chain, f),
+                Endpoint::Data(_) => route.name.as_deref() == Some(name) && f(chain),
+            };
+            chain.pop();
+            if done {
+                return true;
+            }
+        }
+        false
+    }
+
+    pub(super) fn route_params(&self, name: &str) -> Option<Vec<(&str, Option<ParamType>)>> {
+        let mut params: Vec<(&str, Option<ParamType>)> = Vec::new();
+        let mut found = false;
+
+        self.visit_name(name, &mut Vec::new(), &mut |chain| {
+            found = true;
+            for route in chain {
+                for (key, ty) in route.params() {
+                    if params.iter().all(|&(k, _)| k != key) {
+                        params.push((key, ty));
+                    }
+                }
+            }
+            false
+        });
+
+        if found {
+            Some(params)
+        } else {
+            None
+        }
+    }
+
+    pub(super) fn build_url(
+        &self,
+        name: &str,
+        params: &[(&str, &str)],
+    ) -> Result<String, &'static str> {
+        let mut ans = Err("route name not found");
+        self.visit_name(name, &mut Vec::new(), &mut |chain| {
+            ans = build_url_with(chain, params);
+            ans.is_ok()
+        });
+        ans
+    }
+}
+
+fn build_url_with(chain: &[&Route], params: &[(&str, &str)]) -> Result<String, &'static str> {
+    let mut used: SmallVec<[bool; 8]> = smallvec::smallvec![false; params.len()];
+    let mut url = String::new();
+
+    for route in chain {
+        // safety: patterns always start with '/'
+        let parts = route.pattern[1..].split('/').enumerate();
+        for (i, part) in parts {
+            url.push('/');
+            if let Some(key) = route.capture_name(i) {
+                let value = find_param(params, key, &mut used)?;
+                if let Some(c) = route.constraint(i) {
+                    if !c.is_match(value) {
+                        return Err("parameter does not satisfy the constraint");
+                    }
+                }
+                percent::encode_segment(value, &mut url);
+            } else if let Some(a) = route.affix(i) {
+                for token in a.tokens() {
+                    match *token {
+                        Token::Literal(ref lit) => url.push_str(lit),
+                        Token::Capture(ref key) => {
+                            let value = find_param(params, key, &mut used)?;
+                            percent::encode_segment(value, &mut url);
+                        }
+                    }
+                }
+            } else if let Some(key) = route.wildcard.as_deref().filter(|_| part.starts_with('*')) {
+                let value = find_param(params, key, &mut used)?;
+                let value = value.strip_prefix('/').unwrap_or(value);
+                for (i, seg) in value.split('/').enumerate() {
+                    if i > 0 {
+                        url.push('/');
+                    }
+                    percent::encode_segment(seg, &mut url);
+                }
+            } else {
+                url.push_str(part);
+            }
+        }
+    }
+
+    if used.contains(&false) {
+        return Err("unknown parameter");
+    }
+    Ok(url)
+}
+
+impl Route {
+    fn params(&self) -> Vec<(&str, Option<ParamType>)> {
+        let mut params = Vec::new();
+        let parts = self.pattern[1..].split('/').enumerate();
+        for (i, _) in parts {
+            if let Some(key) = self.capture_name(i) {
+                let ty = match self.constraint(i) {
+                    Some(&Constraint::Type(ty)) => Some(ty),
+                    _ => None,
+                };
+                params.push((key, ty));
+            } else if let Some(a) = self.affix(i) {
+                for token in a.tokens() {
+                    if let Token::Capture(ref key) = *token {
+                        params.push((&**key, None));
+                    }
+                }
+            }
+        }
+        if let Some(ref key) = self.wildcard {
+            params.push((&**key, None));
+        }
+        params
+    }
+
+    fn capture_name(&self, pos: usize) -> Option<&str> {
+        self.captures
+            .iter()
+            .find_map(|&(ref name, i)| if i == pos { Some(&**name) } else { None })
+    }
+
+    fn affix(&self, pos: usize) -> Option<&Affix> {
+        self.affixes
+            .iter()
+            .find_map(|&(i, ref a)| if i == pos { Some(a) } else { None })
+    }
+
+    fn constraint(&self, pos: usize) -> Option<&Constraint> {
+        self.constraints
+            .iter()
+            .find_map(|&(i, ref c)| if i == pos { Some(c) } else { None })
+    }
+}
+
+fn find_param<'a>(
+    params: &[(&str, &'a str)],
+    key: &str,
+    used: &mut [bool],
+) -> Result<&'a str, &'static str> {
+    match params.iter().position(|&(k, _)| k == key) {
+        Some(i) => {
+            used[i] = true;
+            Ok(params[i].1)
+        }
+        None => Err("missing parameter"),
+    }
+}
@@ -17,7 +17,11 @@ impl<T> StrMap<T> {
     }
 
     pub fn find(&self, key: &str) -> Option<&T> {
-        match self.find_index(key.as_bytes()) {
+        self.find_bytes(key.as_bytes())
+    }
+
+    pub fn find_bytes(&self, key: &[u8]) -> Option<&T> {
+        match self.find_index(key) {
             Ok(i) => Some(unsafe { self.values.get_unchecked(i) }),
             Err(_) => None,
         }
@@ -36,6 +40,26 @@ impl<T> StrMap<T> {
         unsafe { self.values.get_unchecked_mut(i) }
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &T)> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|k| unsafe { std::str::from_utf8_unchecked(k) });
+        keys.zip(self.values.iter())
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&mut T) -> bool) {
+        let mut i = 0;
+        while i < self.values.len() {
+            if f(&mut self.values[i]) {
+                i += 1;
+            } else {
+                self.values.remove(i);
+                self.keys.remove(i);
+            }
+        }
+    }
+
     fn find_index(&self, key: &[u8]) -> Result<usize, usize> {
         use Ordering::*;
 
@@ -63,7 +87,7 @@ impl<T> StrMap<T> {
             }
         }
         let target: &[u8] = match keys.get(l) {
-            Some(t) => &**t,
+            Some(t) => t,
             None => return Err(keys.len()),
         };
         match cmp(target, key) {
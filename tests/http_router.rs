@@ -25,3 +25,383 @@ fn http_router_macro() {
     assert_eq!(*router.find(&Method::GET, "/v1/u/asd/p/qwe").unwrap().0, 6);
     assert_eq!(*router.find(&Method::POST, "/v1/u/asd/p").unwrap().0, 7);
 }
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_remove() {
+    use nuclear_router::{HttpRouter, Method};
+
+    let mut router: HttpRouter<i32> = HttpRouter::new();
+    router
+        .insert(Method::GET, "/u/:uid", 1)
+        .insert(Method::POST, "/u/:uid", 2);
+
+    assert!(router.remove(&Method::PUT, "/u/:uid").is_none());
+    assert_eq!(router.remove(&Method::GET, "/u/:id"), Some(1));
+    assert!(router.find(&Method::GET, "/u/asd").is_none());
+    assert_eq!(*router.find(&Method::POST, "/u/asd").unwrap().0, 2);
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_case_sensitivity() {
+    use nuclear_router::{CaseSensitivity, HttpRouter, Method};
+
+    let mut router: HttpRouter<i32> = HttpRouter::new();
+    router
+        .insert(Method::GET, "/api", 1)
+        .insert(Method::POST, "/api", 2)
+        .insert(Method::POST, "/API", 3);
+
+    assert!(router
+        .try_set_case_sensitivity(CaseSensitivity::AsciiInsensitive)
+        .is_err());
+    assert_eq!(router.case_sensitivity(), CaseSensitivity::Sensitive);
+    assert!(router.find(&Method::GET, "/Api").is_none());
+
+    assert_eq!(router.remove(&Method::POST, "/API"), Some(3));
+    router.set_case_sensitivity(CaseSensitivity::AsciiInsensitive);
+    assert_eq!(*router.find(&Method::GET, "/Api").unwrap().0, 1);
+    assert!(router.try_insert(Method::GET, "/API", 4).is_err());
+    assert!(router.try_insert(Method::PUT, "/API", 4).is_ok());
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_url_for() {
+    use nuclear_router::{HttpRouter, Method};
+
+    let mut router: HttpRouter<i32> = HttpRouter::new();
+    router.insert(Method::GET, "/u/:uid", 1).insert_named(
+        Method::POST,
+        "user.post",
+        "/u/:uid/p",
+        2,
+    );
+
+    assert_eq!(
+        router.url_for("user.post", &[("uid", "asd")]).unwrap(),
+        "/u/asd/p"
+    );
+    assert!(router.url_for("user.post", &[]).is_err());
+    assert!(router.url_for("user", &[("uid", "asd")]).is_err());
+
+    // a name belongs to a single route whatever its method
+    assert!(router
+        .try_insert_named(Method::GET, "user.post", "/b", 3)
+        .is_err());
+    assert!(router.find(&Method::GET, "/b").is_none());
+    assert!(router
+        .try_nest("/v1", |v| {
+            v.insert_named(Method::PUT, "user.post", "/c", 4);
+        })
+        .is_err());
+    assert!(router.find(&Method::PUT, "/v1/c").is_none());
+    assert_eq!(
+        router.url_for("user.post", &[("uid", "asd")]).unwrap(),
+        "/u/asd/p"
+    );
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_routes() {
+    use nuclear_router::{http_router, HttpRouter, Method};
+
+    let router: HttpRouter<i32> = http_router! {
+        POST "/u/:uid/p" => 2,
+        GET "/u/:uid/p/:pid" => 1,
+        @ "/v1" => http_router!{
+            GET "/info" => 3
+        }
+    };
+
+    let routes: Vec<(&Method, String, i32)> = router.routes().map(|(m, p, &t)| (m, p, t)).collect();
+    assert_eq!(
+        routes,
+        [
+            (&Method::GET, "/u/:uid/p/:pid".to_owned(), 1),
+            (&Method::GET, "/v1/info".to_owned(), 3),
+            (&Method::POST, "/u/:uid/p".to_owned(), 2),
+        ]
+    );
+}
+
+#[cfg(all(feature = "http-router", feature = "serde"))]
+#[test]
+fn http_router_config() {
+    use nuclear_router::{HttpRouter, Method, RouterConfig};
+
+    let json = r#"{
+        "routes": [
+            { "method": "GET", "pattern": "/u/:uid", "handler": "user", "name": "user" },
+            { "method": "POST", "pattern": "/u/:uid", "handler": "post" }
+        ],
+        "nested": [
+            {
+                "prefix": "/v1",
+                "routes": [{ "method": "GET", "pattern": "/info", "handler": "info" }]
+            }
+        ]
+    }"#;
+    let config: RouterConfig = serde_json::from_str(json).unwrap();
+
+    let handlers = ["user", "post", "info"];
+    let resolve = |key: &str| handlers.iter().position(|&h| h == key);
+    let router: HttpRouter<usize> = HttpRouter::from_config(&config, resolve).unwrap();
+
+    assert_eq!(*router.find(&Method::GET, "/u/asd").unwrap().0, 0);
+    assert_eq!(*router.find(&Method::POST, "/u/asd").unwrap().0, 1);
+    assert_eq!(*router.find(&Method::GET, "/v1/info").unwrap().0, 2);
+    assert_eq!(router.url_for("user", &[("uid", "asd")]).unwrap(), "/u/asd");
+
+    let dumped = router.to_config(|&i| handlers[i].to_owned());
+    assert_eq!(
+        serde_json::to_value(&dumped).unwrap(),
+        serde_json::json!({
+            "routes": [
+                { "method": "GET", "pattern": "/u/:uid", "handler": "user", "name": "user" },
+                { "method": "GET", "pattern": "/v1/info", "handler": "info" },
+                { "method": "POST", "pattern": "/u/:uid", "handler": "post" }
+            ]
+        })
+    );
+    let reloaded: HttpRouter<usize> = HttpRouter::from_config(&dumped, resolve).unwrap();
+    assert_eq!(*reloaded.find(&Method::GET, "/v1/info").unwrap().0, 2);
+
+    let mut bad = config.clone();
+    bad.nested[0].router.routes[0].pattern = "info".into();
+    let err = HttpRouter::from_config(&bad, resolve).unwrap_err();
+    assert_eq!(err.entry(), "nested[0].routes[0]");
+    assert_eq!(
+        err.to_string(),
+        "nested[0].routes[0]: pattern must start with '/'"
+    );
+
+    let mut bad = config;
+    bad.routes[1].handler = "missing".into();
+    let err = HttpRouter::from_config(&bad, resolve).unwrap_err();
+    assert_eq!(err.to_string(), "routes[1]: unknown handler key");
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_allowed_methods() {
+    use nuclear_router::{HttpRouter, Method};
+
+    let mut router: HttpRouter<i32> = HttpRouter::new();
+    router
+        .insert(Method::POST, "/u/:uid", 1)
+        .insert(Method::GET, "/u/:uid", 2)
+        .insert(Method::GET, "/p/:pid", 3);
+
+    assert_eq!(
+        router.allowed_methods("/u/asd"),
+        [&Method::GET, &Method::POST]
+    );
+    assert_eq!(router.allowed_methods("/p/asd"), [&Method::GET]);
+    assert!(router.allowed_methods("/x/asd").is_empty());
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_any_method() {
+    use nuclear_router::{http_router, HttpRouter, Method};
+
+    let mut router: HttpRouter<i32> = http_router! {
+        GET | POST "/u/:uid" => 1,
+        * "/u/:uid/*path" => 2,
+        * "/p/:pid" => 3,
+        DELETE "/p/*path" => 4
+    };
+
+    assert_eq!(*router.find(&Method::GET, "/u/asd").unwrap().0, 1);
+    assert_eq!(*router.find(&Method::POST, "/u/asd").unwrap().0, 1);
+    assert!(router.find(&Method::PUT, "/u/asd").is_none());
+    assert_eq!(*router.find(&Method::PUT, "/u/asd/p").unwrap().0, 2);
+
+    // a route of the request method takes precedence
+    assert_eq!(*router.find(&Method::DELETE, "/p/asd").unwrap().0, 4);
+    assert_eq!(*router.find(&Method::GET, "/p/asd").unwrap().0, 3);
+    let all: Vec<i32> = router
+        .find_all(&Method::DELETE, "/p/asd")
+        .into_iter()
+        .map(|(&t, _)| t)
+        .collect();
+    assert_eq!(all, [4, 3]);
+
+    assert_eq!(
+        router.allowed_methods("/u/asd"),
+        [&Method::GET, &Method::POST]
+    );
+    assert_eq!(
+        router
+            .routes()
+            .filter(|(m, _, _)| m.as_str() == "*")
+            .count(),
+        2
+    );
+
+    *router.find_mut(&Method::PATCH, "/p/asd").unwrap().0 = 5;
+    assert_eq!(*router.find(&Method::PUT, "/p/asd").unwrap().0, 5);
+    assert_eq!(router.remove_any("/p/:pid"), Some(5));
+    assert!(router.find(&Method::PUT, "/p/asd").is_none());
+
+    // a collision leaves the router unchanged
+    router.insert(Method::POST, "/q", 6);
+    let methods = [Method::GET, Method::PUT, Method::POST];
+    assert!(router.try_insert_methods(&methods, "/q", 7).is_err());
+    assert!(router.find(&Method::GET, "/q").is_none());
+    assert_eq!(router.allowed_methods("/q"), [&Method::POST]);
+    assert!(router.routes().all(|(m, _, _)| *m != Method::PUT));
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn host_router() {
+    use nuclear_router::{http_router, HostRouter, HttpRouter, Method, OwnedCaptures};
+
+    let mut router: HostRouter<i32> = HostRouter::new();
+    router
+        .insert("*.example.com", http_router! { GET "/" => 1 })
+        .insert(":tenant.example.com", http_router! { GET "/u/:uid" => 2 })
+        .insert("api.example.com", http_router! { GET "/u/:uid" => 3 })
+        .set_default(http_router! { GET "/u/:uid" => 4 });
+
+    assert!(router
+        .try_insert(":t.example.com", HttpRouter::new())
+        .is_err());
+    assert!(router.try_insert("a.*.com", HttpRouter::new()).is_err());
+
+    let find = |host: &str, path: &str| router.find(&Method::GET, host, path).map(|(&t, _)| t);
+    assert_eq!(find("api.example.com", "/u/asd"), Some(3));
+    assert_eq!(find("API.Example.com:8080", "/u/asd"), Some(3));
+    assert_eq!(find("acme.example.com", "/u/asd"), Some(2));
+    assert_eq!(find("a.b.example.com", "/"), Some(1));
+    assert_eq!(find("example.com", "/u/asd"), Some(4));
+    assert_eq!(find("acme.example.com", "/"), None);
+
+    let (_, caps) = router
+        .find(&Method::GET, "acme.example.com", "/u/asd")
+        .unwrap();
+    assert_eq!(&*caps, &[("tenant", "acme"), ("uid", "asd")]);
+    let owned = OwnedCaptures::new(&caps);
+    assert_eq!(owned.get("tenant"), Some("acme"));
+    assert_eq!(owned.get("uid"), Some("asd"));
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_guard() {
+    use nuclear_router::{Guard, Method};
+
+    use http::{Request, StatusCode};
+
+    let parts = |uri: &str, headers: &[(&str, &str)]| {
+        let mut req = Request::get(uri);
+        for &(k, v) in headers {
+            req = req.header(k, v);
+        }
+        req.body(()).unwrap().into_parts().0
+    };
+
+    let json = Guard::accept("application/json");
+    assert!(json.check(&parts("/", &[])));
+    assert!(json.check(&parts("/", &[("accept", "text/html, application/*;q=0.5")])));
+    assert!(json.check(&parts("/", &[("accept", "*/*")])));
+    assert!(!json.check(&parts(
+        "/",
+        &[("accept", "text/html, application/json;q=0")]
+    )));
+    assert_eq!(json.rejection(), Some(StatusCode::NOT_ACCEPTABLE));
+
+    let json = Guard::content_type("application/json");
+    assert!(json.check(&parts(
+        "/",
+        &[("content-type", "Application/JSON; charset=utf-8")]
+    )));
+    assert!(!json.check(&parts("/", &[("content-type", "text/plain")])));
+    assert!(!json.check(&parts("/", &[])));
+    assert_eq!(json.rejection(), Some(StatusCode::UNSUPPORTED_MEDIA_TYPE));
+
+    let preview = Guard::query("preview", None);
+    assert!(preview.check(&parts("/?a=1&preview", &[])));
+    assert!(!preview.check(&parts("/?a=1", &[])));
+    let v2 = Guard::query("v", Some("2"));
+    assert!(v2.check(&parts("/?v=2", &[])));
+    assert!(!v2.check(&parts("/?v=1", &[])));
+    assert_eq!(v2.rejection(), None);
+
+    let mut router = nuclear_router::HttpRouter::new();
+    router
+        .insert_guarded(Method::GET, "/doc", vec![Guard::accept("text/html")], 1)
+        .insert_guarded(Method::GET, "/doc", vec![Guard::query("raw", None)], 2)
+        .insert(Method::GET, "/doc", 3);
+    assert!(router.try_insert(Method::GET, "/doc", 4).is_err());
+    assert_eq!(*router.find(&Method::GET, "/doc").unwrap().0, 3);
+    assert_eq!(
+        router.routes().map(|(_, _, &t)| t).collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    *router.find_mut(&Method::GET, "/doc").unwrap().0 = 5;
+    {
+        let found = router.find_all(&Method::GET, "/doc");
+        let found: Vec<usize> = found.iter().map(|(&t, _)| t).collect();
+        assert_eq!(found, [1, 2, 5]);
+    }
+
+    assert_eq!(router.remove(&Method::GET, "/doc"), Some(5));
+    assert_eq!(router.remove(&Method::GET, "/doc"), None);
+    assert_eq!(*router.find(&Method::GET, "/doc").unwrap().0, 1);
+    assert_eq!(router.remove_all(&Method::GET, "/doc"), [1, 2]);
+    assert!(router.find(&Method::GET, "/doc").is_none());
+
+    // a guarded route inserted after the route without guards is still tried first
+    router.insert(Method::GET, "/x/:id", 1).insert_guarded(
+        Method::GET,
+        "/x/:id",
+        vec![Guard::query("v", None)],
+        2,
+    );
+    {
+        let found = router.find_all(&Method::GET, "/x/7");
+        assert_eq!(found.iter().map(|(&t, _)| t).collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(found[1].1.get("id"), Some("7"));
+    }
+    assert_eq!(
+        router.routes().map(|(_, _, &t)| t).collect::<Vec<_>>(),
+        [2, 1]
+    );
+    assert_eq!(router.remove(&Method::GET, "/x/:id"), Some(1));
+    assert_eq!(router.remove(&Method::GET, "/x/:id"), Some(2));
+    assert!(router.routes().next().is_none());
+
+    // the guarded routes are kept when the pattern is written differently
+    router
+        .insert_guarded(Method::GET, "/u/:id", vec![Guard::query("v", None)], 1)
+        .insert(Method::GET, "/u/:id", 2);
+    assert_eq!(router.remove(&Method::GET, "/u/:uid"), Some(2));
+    assert_eq!(*router.find(&Method::GET, "/u/7").unwrap().0, 1);
+    assert_eq!(router.remove(&Method::GET, "/u/:uid"), Some(1));
+
+    router.nest("/v1", |r| {
+        r.insert_guarded(Method::GET, "/x", vec![Guard::query("v", None)], 1)
+            .insert(Method::GET, "/x", 2);
+    });
+    assert_eq!(router.remove(&Method::GET, "/v1/x"), Some(2));
+    assert_eq!(*router.find(&Method::GET, "/v1/x").unwrap().0, 1);
+}
+
+#[cfg(feature = "http-router")]
+#[test]
+fn http_router_query_params() {
+    use nuclear_router::{OwnedQueryParams, QueryParams};
+
+    let req = http::Request::get("/search?q=a%2Bb&q=c").body(()).unwrap();
+    let params = QueryParams::from_request(&req);
+    assert_eq!(params.get_all("q").collect::<Vec<_>>(), ["a+b", "c"]);
+    assert_eq!(OwnedQueryParams::from_request(&req).get("q"), Some("a+b"));
+
+    let req = http::Request::get("/search").body(()).unwrap();
+    assert!(QueryParams::from_request(&req).is_empty());
+}
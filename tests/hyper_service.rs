@@ -0,0 +1,733 @@
+#[cfg(feature = "hyper-service")]
+use self::helpers::{body, not_found, ok, reply};
+
+#[cfg(feature = "hyper-service")]
+mod helpers {
+    use nuclear_router::{Handler, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::{Body, Request, Response, StatusCode};
+
+    pub async fn ok(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        Ok(Response::new(Body::empty()))
+    }
+
+    pub async fn not_found(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = StatusCode::NOT_FOUND;
+        Ok(res)
+    }
+
+    /// A handler which responds with `text`.
+    pub fn reply(text: &'static str) -> impl Handler + Send + Sync + 'static {
+        move |_, _| async move { Ok::<_, Never>(Response::new(Body::from(text))) }
+    }
+
+    pub async fn body(res: Response<Body>) -> String {
+        let bytes = hyper::body::to_bytes(res.into_body()).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_redirect() {
+    use nuclear_router::{router_service, TrailingSlash};
+
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, StatusCode};
+
+    let mut router = router_service! {
+        GET "/user/:name" => ok,
+        POST "/user/:name" => ok
+    };
+    router
+        .set_trailing_slash(TrailingSlash::Redirect)
+        .set_path_normalization(true);
+    let mut service = router.with_default(not_found);
+
+    let req = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = service.call(req(Method::GET, "/user/asd")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = service
+        .call(req(Method::GET, "/user/asd/?x=1"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers()["location"], "/user/asd?x=1");
+
+    let res = service.call(req(Method::POST, "/user/asd/")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(res.headers()["location"], "/user/asd");
+
+    let res = service.call(req(Method::GET, "/other")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = service
+        .call(req(Method::GET, "//x/../user/asd/?x=1"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers()["location"], "/user/asd?x=1");
+
+    let res = service
+        .call(req(Method::DELETE, "/user/./asd"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(res.headers()["location"], "/user/asd");
+
+    let uri = "/user/%2e%2e/%2e%2e/etc/passwd";
+    let res = service.call(req(Method::GET, uri)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_fallthrough() {
+    use nuclear_router::{router_service, Declined, OwnedCaptures, OwnedQueryParams};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::service::Service;
+    use hyper::{Body, Request, Response};
+
+    async fn reply(text: String) -> Response<Body> {
+        Response::new(Body::from(text))
+    }
+
+    async fn numeric(
+        req: Request<Body>,
+        params: OwnedCaptures,
+    ) -> Result<Response<Body>, Declined> {
+        match params.parse::<u64>("id") {
+            Some(Ok(id)) => Ok(reply(format!("numeric {}", id)).await),
+            _ => Err(Declined::new(req)),
+        }
+    }
+
+    async fn short(req: Request<Body>, params: OwnedCaptures) -> Result<Response<Body>, Declined> {
+        let query = req.extensions().get::<OwnedQueryParams>();
+        let page = query.and_then(|q| q.get("page")).unwrap_or("1");
+        match params.get("path") {
+            Some(path) if path.len() < 10 => Ok(reply(format!("short {} {}", path, page)).await),
+            _ => Err(Declined::new(req)),
+        }
+    }
+
+    async fn not_found(req: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let query = req.extensions().get::<OwnedQueryParams>();
+        let page = query.and_then(|q| q.get("page")).unwrap_or("-");
+        Ok(reply(format!("not found {} {}", req.uri().path(), page)).await)
+    }
+
+    let mut service = router_service! {
+        GET "/item/:id" => numeric,
+        GET "/*path" => short;
+        _ => not_found
+    };
+
+    let req = |uri: &str| Request::get(uri).body(Body::empty()).unwrap();
+
+    let res = service.call(req("/item/42")).await.unwrap();
+    assert_eq!(body(res).await, "numeric 42");
+
+    let res = service.call(req("/item/abc")).await.unwrap();
+    assert_eq!(body(res).await, "short /item/abc 1");
+
+    let res = service.call(req("/item/abc?page=2&page=3")).await.unwrap();
+    assert_eq!(body(res).await, "short /item/abc 2");
+
+    let res = service.call(req("/item/abcd?page=2")).await.unwrap();
+    assert_eq!(body(res).await, "not found /item/abcd 2");
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_method_not_allowed() {
+    use nuclear_router::{router_service, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    async fn teapot(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let mut res = Response::new(Body::from("not allowed"));
+        *res.status_mut() = StatusCode::IM_A_TEAPOT;
+        Ok(res)
+    }
+
+    let req = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let mut service = router_service! {
+        GET "/user/:name" => ok,
+        PUT "/user/:name" => ok;
+        _ => not_found
+    };
+
+    let res = service
+        .call(req(Method::DELETE, "/user/asd"))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()["allow"], "GET, PUT");
+
+    let res = service.call(req(Method::DELETE, "/other")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    service.set_method_not_allowed(Box::new(teapot));
+    let res = service.call(req(Method::POST, "/user/asd")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::IM_A_TEAPOT);
+    assert_eq!(res.headers()["allow"], "GET, PUT");
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_auto_options_head() {
+    use nuclear_router::{router_service, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    async fn hello(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let mut res = Response::new(Body::from("hello"));
+        res.headers_mut().insert("x-hello", "1".parse().unwrap());
+        Ok(res)
+    }
+
+    let req = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let mut service = router_service! {
+        GET "/hello" => hello,
+        POST "/hello" => hello;
+        _ => not_found
+    };
+
+    let res = service.call(req(Method::HEAD, "/hello")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()["allow"], "GET, POST");
+
+    service.set_auto_head(true).set_auto_options(true);
+
+    let res = service.call(req(Method::HEAD, "/hello")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers()["x-hello"], "1");
+    assert_eq!(body(res).await, "");
+
+    let res = service.call(req(Method::OPTIONS, "/hello")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(res.headers()["allow"], "GET, HEAD, OPTIONS, POST");
+
+    let res = service.call(req(Method::OPTIONS, "/other")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let res = service.call(req(Method::DELETE, "/hello")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(res.headers()["allow"], "GET, HEAD, OPTIONS, POST");
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_any_method() {
+    use nuclear_router::{router_service, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    async fn read(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        Ok(Response::new(Body::from("read")))
+    }
+
+    async fn fallback(req: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        Ok(Response::new(Body::from(req.method().to_string())))
+    }
+
+    let req = |method: Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let mut service = router_service! {
+        GET | HEAD "/item" => read,
+        * "/item" => fallback;
+        _ => not_found
+    };
+
+    let res = service.call(req(Method::GET, "/item")).await.unwrap();
+    assert_eq!(body(res).await, "read");
+    let res = service.call(req(Method::HEAD, "/item")).await.unwrap();
+    assert_eq!(body(res).await, "read");
+    let res = service.call(req(Method::PATCH, "/item")).await.unwrap();
+    assert_eq!(body(res).await, "PATCH");
+    let res = service.call(req(Method::PATCH, "/other")).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_host() {
+    use nuclear_router::{router_service, HostRouter, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+
+    use hyper::service::Service;
+    use hyper::{Body, Request, Response};
+
+    async fn tenant(_: Request<Body>, params: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let text = format!(
+            "{} {}",
+            params.get("tenant").unwrap(),
+            params.get("id").unwrap()
+        );
+        Ok(Response::new(Body::from(text)))
+    }
+
+    async fn admin(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        Ok(Response::new(Body::from("admin")))
+    }
+
+    async fn not_found(_: Request<Body>, _: OwnedCaptures) -> Result<Response<Body>, Never> {
+        Ok(Response::new(Body::from("not found")))
+    }
+
+    let mut router = HostRouter::new();
+    router
+        .insert(
+            ":tenant.example.com",
+            router_service! { GET "/item/:id" => tenant },
+        )
+        .insert("admin.example.com", router_service! { GET "/" => admin });
+    let mut service = router.with_default(not_found);
+
+    let req = |host: &str, uri: &str| {
+        Request::get(uri)
+            .header("host", host)
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    let res = service
+        .call(req("acme.example.com", "/item/42"))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "acme 42");
+
+    let res = service
+        .call(req("admin.example.com:8080", "/"))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "admin");
+
+    let res = service.call(req("other.com", "/")).await.unwrap();
+    assert_eq!(body(res).await, "not found");
+
+    let res = Request::get("http://beta.example.com/item/1")
+        .body(Body::empty())
+        .unwrap();
+    let res = service.call(res).await.unwrap();
+    assert_eq!(body(res).await, "beta 1");
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_guards() {
+    use nuclear_router::{Guard, HttpRouter};
+
+    use hyper::header::HeaderName;
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, StatusCode};
+
+    let version = HeaderName::from_static("x-api-version");
+    let mut router = HttpRouter::new();
+    router
+        .route_guarded(
+            Method::GET,
+            "/doc",
+            vec![Guard::header(version, "2")],
+            reply("v2"),
+        )
+        .route_guarded(
+            Method::GET,
+            "/doc",
+            vec![Guard::query("raw", None)],
+            reply("raw"),
+        )
+        .route_guarded(
+            Method::GET,
+            "/doc",
+            vec![Guard::accept("application/json")],
+            reply("json"),
+        )
+        .route_guarded(
+            Method::GET,
+            "/doc",
+            vec![Guard::accept("text/html")],
+            reply("html"),
+        )
+        .route_guarded(
+            Method::POST,
+            "/doc",
+            vec![Guard::content_type("application/json")],
+            reply("posted"),
+        );
+    let mut service = router.with_default(not_found);
+
+    let req = |method: Method, uri: &str, headers: &[(&str, &str)]| {
+        let mut req = Request::builder().method(method).uri(uri);
+        for &(k, v) in headers {
+            req = req.header(k, v);
+        }
+        req.body(Body::empty()).unwrap()
+    };
+
+    let res = service
+        .call(req(Method::GET, "/doc", &[("x-api-version", "2")]))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "v2");
+    let res = service
+        .call(req(Method::GET, "/doc?raw", &[]))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "raw");
+    let res = service.call(req(Method::GET, "/doc", &[])).await.unwrap();
+    assert_eq!(body(res).await, "json");
+    let res = service
+        .call(req(Method::GET, "/doc", &[("accept", "text/html")]))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "html");
+    let res = service
+        .call(req(Method::GET, "/doc", &[("accept", "image/png")]))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+
+    let json = [("content-type", "application/json")];
+    let res = service
+        .call(req(Method::POST, "/doc", &json))
+        .await
+        .unwrap();
+    assert_eq!(body(res).await, "posted");
+    let xml = [("content-type", "application/xml")];
+    let res = service.call(req(Method::POST, "/doc", &xml)).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+    let res = service.call(req(Method::GET, "/other", &[])).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "hyper-service")]
+#[tokio::test]
+async fn hyper_service_middleware() {
+    use nuclear_router::{Guard, HttpRouter, Middleware, Next, TrailingSlash};
+
+    use std::error::Error;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    use hyper::header::{HeaderValue, AUTHORIZATION};
+    use hyper::service::Service;
+    use hyper::{Body, Method, Request, Response, StatusCode};
+
+    type BoxFuture<'a> = Pin<
+        Box<dyn Future<Output = Result<Response<Body>, Box<dyn Error + Send + Sync>>> + Send + 'a>,
+    >;
+
+    /// Records the pattern and the captures of each call.
+    struct Log(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl Middleware for Log {
+        fn call<'a>(&'a self, req: Request<Body>, next: Next<'a>) -> BoxFuture<'a> {
+            let id = next.params().get("id").unwrap_or("-");
+            let line = format!("{} {} {}", self.0, next.pattern().unwrap_or("-"), id);
+            self.1.lock().unwrap().push(line);
+            next.run(req)
+        }
+    }
+
+    struct Auth;
+
+    impl Middleware for Auth {
+        fn call<'a>(&'a self, req: Request<Body>, next: Next<'a>) -> BoxFuture<'a> {
+            if req.headers().contains_key(AUTHORIZATION) {
+                return next.run(req);
+            }
+            Box::pin(async move {
+                let mut res = Response::new(Body::empty());
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+                Ok(res)
+            })
+        }
+    }
+
+    struct Header(&'static str);
+
+    impl Middleware for Header {
+        fn call<'a>(&'a self, req: Request<Body>, next: Next<'a>) -> BoxFuture<'a> {
+            Box::pin(async move {
+                let mut res = next.run(req).await?;
+                let value = HeaderValue::from_static(self.0);
+                res.headers_mut().append("x-layer", value);
+                Ok(res)
+            })
+        }
+    }
+
+    fn layers(res: &Response<Body>) -> Vec<&str> {
+        let values = res.headers().get_all("x-layer").iter();
+        values.map(|v| v.to_str().unwrap()).collect()
+    }
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let mut router = HttpRouter::new();
+    router
+        .get("/", reply("index"))
+        .route_with(Method::GET, "/user/:id", Header("route"), reply("user"))
+        .route_guarded(
+            Method::POST,
+            "/upload",
+            vec![Guard::content_type("image/png")],
+            reply("upload"),
+        )
+        .nest("/admin", |r| {
+            r.get("/post/:id", reply("post"));
+            r.layer(Auth).layer(Header("admin"));
+        })
+        .set_trailing_slash(TrailingSlash::Redirect)
+        .set_path_normalization(true);
+
+    let mut service = router.with_default(not_found);
+    service
+        .set_auto_options(true)
+        .add_middleware(Log("a", Arc::clone(&log)))
+        .add_middleware(Header("global"));
+
+    let req = Request::get("/user/7").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(layers(&res), ["route", "global"]);
+
+    let req = Request::get("/admin/post/3").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(layers(&res), ["admin", "global"]);
+
+    let req = Request::get("/admin/post/3")
+        .header(AUTHORIZATION, "token")
+        .body(Body::empty())
+        .unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(layers(&res), ["admin", "global"]);
+
+    let req = Request::get("/").body(Body::empty()).unwrap();
+    assert_eq!(layers(&service.call(req).await.unwrap()), ["global"]);
+
+    let req = Request::get("/missing").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    assert_eq!(layers(&res), ["global"]);
+
+    // the responses of the service itself go through the global middlewares
+    let req = Request::delete("/user/7").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(layers(&res), ["global"]);
+
+    let req = Request::options("/user/7").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::NO_CONTENT);
+    assert_eq!(layers(&res), ["global"]);
+
+    let req = Request::post("/upload").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(layers(&res), ["global"]);
+
+    let req = Request::get("/user/7/").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(layers(&res), ["global"]);
+
+    let req = Request::get("//user/7").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+    assert_eq!(res.headers()["location"], "/user/7");
+    assert_eq!(layers(&res), ["global"]);
+
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "a /user/:id 7",
+            "a /admin/post/:id 3",
+            "a /admin/post/:id 3",
+            "a / -",
+            "a - -",
+            "a - -",
+            "a - -",
+            "a - -",
+            "a - -",
+            "a - -",
+        ]
+    );
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn hyper_service_tower() {
+    use nuclear_router::{HttpRouter, OwnedCaptures};
+
+    use std::convert::Infallible as Never;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+
+    use hyper::header::HeaderValue;
+    use hyper::{Body, Method, Request, Response};
+    use tower_layer::Layer;
+    use tower_service::Service;
+
+    type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+    struct NoopWaker;
+
+    impl Wake for NoopWaker {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    /// Echoes the capture `id` when the gate is open.
+    #[derive(Clone)]
+    struct Gated(Arc<AtomicBool>);
+
+    impl Service<Request<Body>> for Gated {
+        type Response = Response<Body>;
+        type Error = Never;
+        type Future = BoxFuture<Result<Response<Body>, Never>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Never>> {
+            if self.0.load(Ordering::SeqCst) {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let params = req.extensions().get::<OwnedCaptures>().unwrap();
+            let id = params.get("id").unwrap().to_owned();
+            Box::pin(async move { Ok(Response::new(Body::from(id))) })
+        }
+    }
+
+    struct AddHeader;
+
+    impl<S> Layer<S> for AddHeader {
+        type Service = AddHeaderService<S>;
+
+        fn layer(&self, inner: S) -> Self::Service {
+            AddHeaderService(inner)
+        }
+    }
+
+    #[derive(Clone)]
+    struct AddHeaderService<S>(S);
+
+    impl<S> Service<Request<Body>> for AddHeaderService<S>
+    where
+        S: Service<Request<Body>, Response = Response<Body>>,
+        S::Future: Send + 'static,
+    {
+        type Response = Response<Body>;
+        type Error = S::Error;
+        type Future = BoxFuture<Result<Response<Body>, S::Error>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), S::Error>> {
+            self.0.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: Request<Body>) -> Self::Future {
+            let fut = self.0.call(req);
+            Box::pin(async move {
+                let mut res = fut.await?;
+                let value = HeaderValue::from_static("tower");
+                res.headers_mut().insert("x-layer", value);
+                Ok(res)
+            })
+        }
+    }
+
+    async fn item(_: Request<Body>, params: OwnedCaptures) -> Result<Response<Body>, Never> {
+        let id = params.get("id").unwrap().to_owned();
+        Ok(Response::new(Body::from(id)))
+    }
+
+    let open = Arc::new(AtomicBool::new(false));
+
+    let mut router = HttpRouter::new();
+    router
+        .route_service(Method::GET, "/gated/:id", Gated(Arc::clone(&open)))
+        .get("/plain/:id", item)
+        .nest("/api", |r| {
+            r.get("/item/:id", item);
+            r.service_layer(AddHeader);
+        });
+    let mut service = router.with_default(not_found);
+
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    assert!(service.poll_ready(&mut cx).is_pending());
+    open.store(true, Ordering::SeqCst);
+    assert!(matches!(service.poll_ready(&mut cx), Poll::Ready(Ok(()))));
+
+    let req = Request::get("/gated/5").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(body(res).await, "5");
+
+    let req = Request::get("/api/item/9").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert_eq!(res.headers()["x-layer"], "tower");
+    assert_eq!(body(res).await, "9");
+
+    let req = Request::get("/plain/3").body(Body::empty()).unwrap();
+    let res = service.call(req).await.unwrap();
+    assert!(!res.headers().contains_key("x-layer"));
+    assert_eq!(body(res).await, "3");
+
+    let mut shared = service.into_shared();
+    open.store(false, Ordering::SeqCst);
+    assert!(Service::poll_ready(&mut shared, &mut cx).is_pending());
+}
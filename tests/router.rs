@@ -105,3 +105,971 @@ fn router_nested() {
 
     assert_eq!(*router.find("/v1/u/asd/p/qwe").unwrap().0, 1);
 }
+
+#[test]
+fn router_many_routes() {
+    let mut router: Router<usize> = Router::new();
+    for i in 0..1000 {
+        router.insert(&format!("/item/{}/:name", i), i);
+    }
+    router.insert("/item/:id/:name", 1000);
+
+    for i in (0..1000).step_by(37) {
+        let url = format!("/item/{}/asd", i);
+        let ret = router.find(&url).unwrap();
+        assert_eq!(*ret.0, i);
+        assert_eq!(&*ret.1, &[("name", "asd")]);
+    }
+    assert_eq!(*router.find("/item/1000/asd").unwrap().0, 1000);
+    assert!(router.try_insert("/item/999/:id", 1001).is_err());
+}
+
+#[test]
+fn router_priority() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/:a/:b", 1)
+        .insert("/:a/me", 2)
+        .insert("/u/:b", 3)
+        .insert("/u/me", 4)
+        .insert("/u/*path", 5);
+
+    assert_eq!(*router.find("/x/y").unwrap().0, 1);
+    assert_eq!(*router.find("/x/me").unwrap().0, 2);
+    assert_eq!(*router.find("/u/y").unwrap().0, 3);
+    assert_eq!(*router.find("/u/me").unwrap().0, 4);
+    assert_eq!(*router.find("/u/y/z").unwrap().0, 5);
+}
+
+#[test]
+fn router_deep_pattern() {
+    let mut router: Router<usize> = Router::new();
+
+    let mut dynamic = String::new();
+    let mut static_tail = String::new();
+    let mut url = String::new();
+    for i in 0..100 {
+        dynamic.push_str(&format!("/:p{}", i));
+        if i == 0 {
+            static_tail.push_str("/:p0");
+        } else {
+            static_tail.push_str(&format!("/s{}", i));
+        }
+        url.push_str(&format!("/s{}", i));
+    }
+
+    router.insert(&dynamic, 1);
+    router.insert(&static_tail, 2);
+    router.insert(&format!("{}/*rest", url), 3);
+
+    assert_eq!(*router.find(&url).unwrap().0, 2);
+    assert_eq!(router.find(&url).unwrap().1.get("p0"), Some("s0"));
+    assert_eq!(*router.find(&url.replace("/s99", "/x")).unwrap().0, 1);
+    assert_eq!(*router.find(&format!("{}/a/b", url)).unwrap().0, 3);
+    assert!(router
+        .try_insert(&dynamic.replace(":p5", ":q5"), 4)
+        .is_err());
+}
+
+#[test]
+fn router_remove() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/u/:id", 1)
+        .insert("/u/me", 2)
+        .insert("/u/:id/*path", 3)
+        .nest("/v1", |v1| {
+            v1.insert("/info", 4).insert("/u/:uid", 5);
+        })
+        .insert("/explore", 6);
+
+    assert!(router.remove("/u/:uid/:pid").is_none());
+    assert!(router.remove("/u/other").is_none());
+    assert!(router.remove("u/me").is_none());
+
+    assert_eq!(router.remove("/u/me"), Some(2));
+    assert_eq!(*router.find("/u/me").unwrap().0, 1);
+    assert_eq!(router.remove("/u/:uid"), Some(1));
+    assert!(router.find("/u/me").is_none());
+    assert_eq!(*router.find("/u/me/a/b").unwrap().0, 3);
+    assert_eq!(*router.find("/explore").unwrap().0, 6);
+
+    assert_eq!(router.remove("/v1/u/:id"), Some(5));
+    assert!(router.find("/v1/u/asd").is_none());
+    assert_eq!(*router.find("/v1/info").unwrap().0, 4);
+    assert_eq!(router.remove("/v1/info"), Some(4));
+    assert!(router.try_insert("/v1/*path", 7).is_ok());
+
+    assert_eq!(router.remove("/u/:id/*filepath"), Some(3));
+    assert_eq!(router.remove("/v1/*path"), Some(7));
+    assert_eq!(router.remove("/explore"), Some(6));
+    assert!(router.is_empty());
+    assert!(router.find("/explore").is_none());
+
+    router.insert("/u/:id", 8);
+    assert_eq!(*router.find("/u/me").unwrap().0, 8);
+}
+
+#[test]
+fn router_url_for() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .nest("/user/:user_id", |user| {
+            user.insert_named("user.post", "/post/:post_id", 1)
+                .insert_named("user.file", "/file/*filepath", 2)
+                .insert_named("user.home", "/", 3);
+        })
+        .insert_named("explore", "/explore", 4);
+
+    let url = router
+        .url_for("user.post", &[("user_id", "asd"), ("post_id", "123")])
+        .unwrap();
+    assert_eq!(url, "/user/asd/post/123");
+    assert_eq!(*router.find(&url).unwrap().0, 1);
+
+    let url = router
+        .url_for(
+            "user.file",
+            &[("user_id", "a b"), ("filepath", "/home/.bashrc")],
+        )
+        .unwrap();
+    assert_eq!(url, "/user/a%20b/file/home/.bashrc");
+
+    let url = router.url_for("user.home", &[("user_id", "x/y")]).unwrap();
+    assert_eq!(url, "/user/x%2Fy/");
+
+    assert_eq!(router.url_for("explore", &[]).unwrap(), "/explore");
+
+    assert!(router.url_for("user.post", &[("user_id", "asd")]).is_err());
+    assert!(router.url_for("explore", &[("id", "1")]).is_err());
+    assert!(router.url_for("unknown", &[]).is_err());
+
+    assert!(router.try_insert_named("explore", "/other", 5).is_err());
+    assert!(router
+        .try_nest("/v1", |v1| {
+            v1.insert_named("user.post", "/p/:id", 6);
+        })
+        .is_err());
+}
+
+#[test]
+fn router_constraint() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert(r"/user/:id<\d+>", 1)
+        .insert("/user/:name<[a-z]+>", 2)
+        .insert("/user/:other", 3)
+        .insert(r"/file/:name<[a-z]+\.txt>", 4)
+        .insert_named("user", r"/u/:id<\d+>", 5);
+
+    {
+        let ret = router.find("/user/123").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(&*ret.1, &[("id", "123")]);
+    }
+    assert_eq!(*router.find("/user/asd").unwrap().0, 2);
+    assert_eq!(*router.find("/user/Asd1").unwrap().0, 3);
+    assert_eq!(*router.find("/file/a.txt").unwrap().0, 4);
+    assert!(router.find("/file/a.txt.exe").is_none());
+    assert!(router.find("/file/atxt").is_none());
+
+    assert!(router.try_insert(r"/user/:uid<\d+>", 5).is_err());
+    assert!(router.try_insert(r"/user/:uid<\d+", 5).is_err());
+    assert!(router.try_insert("/user/:<[a-z]>", 5).is_err());
+    assert!(router.try_insert("/user/:id<(>", 5).is_err());
+    let err = router.try_insert("/date/:d<\\d+/\\d+>", 5).unwrap_err();
+    assert_eq!(err.to_string(), "capture constraint can not contain '/'");
+
+    assert_eq!(router.url_for("user", &[("id", "123")]).unwrap(), "/u/123");
+    assert!(router.url_for("user", &[("id", "asd")]).is_err());
+
+    assert!(router.remove("/user/:id<[0-9]+>").is_none());
+    assert_eq!(router.remove(r"/user/:id<\d+>"), Some(1));
+    assert_eq!(*router.find("/user/123").unwrap().0, 3);
+}
+
+#[test]
+fn router_param_type() {
+    use nuclear_router::ParamType;
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .nest("/u/:uid:u32", |u| {
+            u.insert_named("post", "/post/:id:u64", 1)
+                .insert("/post/:slug:slug", 2)
+                .insert("/post/:other", 3);
+        })
+        .insert("/obj/:key:uuid", 4)
+        .insert("/n/:x:i8", 5);
+
+    assert_eq!(*router.find("/u/1/post/123").unwrap().0, 1);
+    assert_eq!(*router.find("/u/1/post/hello-world").unwrap().0, 2);
+    assert_eq!(*router.find("/u/1/post/hello--world").unwrap().0, 3);
+    assert!(router.find("/u/-1/post/123").is_none());
+
+    assert_eq!(
+        *router
+            .find("/obj/67e55044-10b1-426f-9247-bb680e5fe0c8")
+            .unwrap()
+            .0,
+        4
+    );
+    assert!(router.find("/obj/67e55044-10b1-426f-9247").is_none());
+    assert_eq!(*router.find("/n/-128").unwrap().0, 5);
+    assert!(router.find("/n/128").is_none());
+
+    assert_eq!(
+        router.params("post").unwrap(),
+        vec![("uid", Some(ParamType::U32)), ("id", Some(ParamType::U64))]
+    );
+    assert!(router.params("other").is_none());
+
+    assert!(router.try_insert("/n/:y:i8", 6).is_err());
+    assert!(router.try_insert("/n/:y:i16", 6).is_ok());
+    assert!(router.try_insert("/n/:y:float", 7).is_err());
+}
+
+#[test]
+fn router_affix() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert_named("file", "/files/:name.:ext", 1)
+        .insert("/files/:name.txt", 2)
+        .insert("/files/index.html", 3)
+        .insert("/files/:name", 4)
+        .insert("/v:version/items", 5)
+        .insert("/@:username", 6)
+        .insert("/:a/:b-:c/x", 7);
+
+    {
+        let ret = router.find("/files/archive.tar.gz").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(&*ret.1, &[("name", "archive"), ("ext", "tar.gz")]);
+    }
+    // the longer literal is more specific
+    assert_eq!(*router.find("/files/a.txt").unwrap().0, 2);
+    assert_eq!(*router.find("/files/index.html").unwrap().0, 3);
+    assert_eq!(*router.find("/files/README").unwrap().0, 4);
+    assert_eq!(*router.find("/files/.bashrc").unwrap().0, 4);
+    assert_eq!(
+        router.find("/v2/items").unwrap().1.get("version"),
+        Some("2")
+    );
+    assert!(router.find("/v/items").is_none());
+    assert_eq!(
+        router.find("/v中/items").unwrap().1.get("version"),
+        Some("中")
+    );
+    assert_eq!(
+        router.find("/@nugine").unwrap().1.get("username"),
+        Some("nugine")
+    );
+    {
+        let ret = router.find("/q/1-2/x").unwrap();
+        assert_eq!(*ret.0, 7);
+        assert_eq!(&*ret.1, &[("a", "q"), ("b", "1"), ("c", "2")]);
+    }
+
+    assert!(router.try_insert("/files/:base.:extension", 8).is_err());
+    assert!(router.try_insert("/files/:name:ext", 8).is_err());
+    assert!(router.try_insert("/files/x:", 8).is_err());
+    assert!(router.try_insert("/u/:user-id", 8).is_err());
+    assert!(router.try_insert("/u/:from-:to", 8).is_ok());
+
+    assert_eq!(
+        router
+            .url_for("file", &[("name", "a b"), ("ext", "md")])
+            .unwrap(),
+        "/files/a%20b.md"
+    );
+
+    assert_eq!(router.remove("/files/:n.txt"), Some(2));
+    assert_eq!(*router.find("/files/a.txt").unwrap().0, 1);
+}
+
+#[test]
+fn router_optional() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert_named("posts", "/posts/:page?", 1)
+        .insert_named("archive", r"/archive(/:year<\d{4}>(/:month:u8))", 2)
+        .nest("/:version<v[12]>", |v| {
+            v.insert("/info(/:lang)", 3);
+        });
+
+    assert_eq!(*router.find("/posts").unwrap().0, 1);
+    assert_eq!(router.find("/posts").unwrap().1.get("page"), None);
+    assert_eq!(router.find("/posts/2").unwrap().1.get("page"), Some("2"));
+
+    assert_eq!(*router.find("/archive").unwrap().0, 2);
+    {
+        let ret = router.find("/archive/2020/02").unwrap();
+        assert_eq!(*ret.0, 2);
+        assert_eq!(&*ret.1, &[("year", "2020"), ("month", "02")]);
+    }
+    assert!(router.find("/archive/20").is_none());
+    assert_eq!(*router.find("/v1/info").unwrap().0, 3);
+    assert_eq!(*router.find("/v2/info/en").unwrap().0, 3);
+
+    assert_eq!(router.url_for("posts", &[]).unwrap(), "/posts");
+    assert_eq!(
+        router.url_for("posts", &[("page", "2")]).unwrap(),
+        "/posts/2"
+    );
+    assert_eq!(
+        router.url_for("archive", &[("year", "2020")]).unwrap(),
+        "/archive/2020"
+    );
+    assert!(router.url_for("archive", &[("month", "1")]).is_err());
+
+    assert!(router.try_insert("/a(/:x)(/:y)", 4).is_err());
+    assert!(router.find("/a").is_none());
+    assert!(router.try_insert("/b(/:x", 5).is_err());
+    assert!(router.try_insert("/b/:x?y", 5).is_err());
+
+    // an expansion is not removed without the others
+    assert_eq!(router.remove("/posts"), None);
+    assert_eq!(router.remove("/posts/:page"), None);
+    assert_eq!(*router.find("/posts").unwrap().0, 1);
+    assert_eq!(router.remove("/posts/:page?"), Some(1));
+    assert!(router.find("/posts").is_none());
+    assert_eq!(
+        router.remove("/archive(/:year<\\d{4}>(/:month:u8))"),
+        Some(2)
+    );
+    assert!(router.find("/archive").is_none());
+    assert_eq!(router.remove("/:version<v[12]>/info/:lang"), None);
+    assert_eq!(*router.find("/v2/info/en").unwrap().0, 3);
+    assert_eq!(router.remove("/:version<v[12]>/info(/:lang)"), Some(3));
+    assert!(router.find("/v1/info").is_none());
+    assert!(router.is_empty());
+
+    router.insert("/:lang?", 5);
+    assert_eq!(router.find("/").unwrap().1.get("lang"), None);
+    assert_eq!(router.find("/en").unwrap().1.get("lang"), Some("en"));
+    assert!(router.try_insert("/posts?", 6).is_err());
+    assert_eq!(router.remove("/:lang?"), Some(5));
+    router.insert("/posts?", 6);
+    assert_eq!(*router.find("/").unwrap().0, 6);
+    assert_eq!(*router.find("/posts").unwrap().0, 6);
+
+    router.insert("/posts/:id/x?/y", 4);
+    assert_eq!(*router.find("/posts/1/y").unwrap().0, 4);
+    assert_eq!(*router.find("/posts/1/x/y").unwrap().0, 4);
+}
+
+#[test]
+fn router_trailing_slash() {
+    use nuclear_router::TrailingSlash;
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/", 0)
+        .insert("/user/:name", 1)
+        .insert("/post/:id/", 2)
+        .nest("/v1", |v| {
+            v.insert("/info", 3);
+        });
+
+    assert!(router.find("/user/asd/").is_none());
+    assert!(router.find("/post/1").is_none());
+
+    router.set_trailing_slash(TrailingSlash::Ignore);
+    {
+        let ret = router.find("/user/asd/").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(&*ret.1, &[("name", "asd")]);
+        assert_eq!(ret.1.redirect(), None);
+    }
+    assert_eq!(*router.find("/post/1").unwrap().0, 2);
+    assert_eq!(*router.find("/v1/info/").unwrap().0, 3);
+    assert_eq!(router.find("/user/asd").unwrap().1.redirect(), None);
+    assert!(router.find("/post/").is_none());
+
+    router.set_trailing_slash(TrailingSlash::Redirect);
+    {
+        let ret = router.find("/user/asd/").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(ret.1.get("name"), Some("asd"));
+        assert_eq!(ret.1.redirect(), Some("/user/asd"));
+    }
+    {
+        let ret = router.find("/post/1").unwrap();
+        assert_eq!(ret.1.get("id"), Some("1"));
+        assert_eq!(ret.1.redirect(), Some("/post/1/"));
+    }
+    assert_eq!(
+        router.find("/v1/info/").unwrap().1.redirect(),
+        Some("/v1/info")
+    );
+    assert_eq!(router.find("/").unwrap().1.redirect(), None);
+}
+
+#[test]
+fn router_case_insensitive() {
+    use nuclear_router::{CaseSensitivity, TrailingSlash};
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/api/users/:name", 1)
+        .insert("/api/Users/me", 2)
+        .insert("/Straße/:id", 3)
+        .nest("/v1", |v| {
+            v.insert("/info", 4);
+        });
+
+    assert!(router.find("/API/users/Asd").is_none());
+
+    router.set_case_sensitivity(CaseSensitivity::AsciiInsensitive);
+    {
+        let ret = router.find("/API/Users/Asd").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(&*ret.1, &[("name", "Asd")]);
+        assert_eq!(ret.1.canonical_path(), Some("/api/users/Asd"));
+        assert_eq!(ret.1.redirect(), None);
+    }
+    {
+        let ret = router.find("/api/users/asd").unwrap();
+        assert_eq!(ret.1.canonical_path(), None);
+    }
+    assert_eq!(*router.find("/api/users/ME").unwrap().0, 2);
+    assert_eq!(
+        router.find("/API/USERS/me").unwrap().1.canonical_path(),
+        Some("/api/Users/me")
+    );
+    assert_eq!(
+        router.find("/V1/INFO").unwrap().1.canonical_path(),
+        Some("/v1/info")
+    );
+    assert!(router.find("/STRASSE/1").is_none());
+    assert!(router.find("/STRAßE/1").is_some());
+    assert!(router.find("/STRAẞE/1").is_none());
+
+    router.insert("/New/Route", 5);
+    assert_eq!(*router.find("/new/route").unwrap().0, 5);
+    assert_eq!(router.remove("/api/users/:name"), Some(1));
+    assert!(router.find("/API/users/asd").is_none());
+    assert_eq!(*router.find("/API/users/me").unwrap().0, 2);
+
+    router.set_case_sensitivity(CaseSensitivity::UnicodeInsensitive);
+    assert_eq!(
+        router.find("/STRAẞE/1").unwrap().1.canonical_path(),
+        Some("/Stra%C3%9Fe/1")
+    );
+
+    router.set_trailing_slash(TrailingSlash::Redirect);
+    {
+        let ret = router.find("/NEW/ROUTE/").unwrap();
+        assert_eq!(ret.1.canonical_path(), Some("/New/Route"));
+        assert_eq!(ret.1.redirect(), Some("/New/Route"));
+    }
+
+    router.set_case_sensitivity(CaseSensitivity::Sensitive);
+    assert!(router.find("/new/route").is_none());
+
+    let mut router: Router<usize> = Router::new();
+    router.set_case_sensitivity(CaseSensitivity::AsciiInsensitive);
+    router.insert("/api/:id", 1);
+    assert!(router.try_insert("/API/:id", 2).is_err());
+    assert!(router.try_insert("/Api/:id<\\d+>", 2).is_ok());
+
+    let mut router: Router<usize> = Router::new();
+    router.insert("/api", 1).insert("/API", 2);
+    assert!(router
+        .try_set_case_sensitivity(CaseSensitivity::AsciiInsensitive)
+        .is_err());
+    assert_eq!(router.case_sensitivity(), CaseSensitivity::Sensitive);
+    assert!(router.find("/Api").is_none());
+    router.remove("/API");
+    router.set_case_sensitivity(CaseSensitivity::AsciiInsensitive);
+    assert_eq!(*router.find("/Api").unwrap().0, 1);
+
+    let mut router: Router<usize> = Router::new();
+    router.nest("/v1", |v| {
+        v.insert("/info", 1).insert("/INFO", 2);
+    });
+    assert!(router
+        .try_set_case_sensitivity(CaseSensitivity::UnicodeInsensitive)
+        .is_err());
+    assert!(router.find("/v1/Info").is_none());
+}
+
+#[test]
+fn router_percent_decoding() {
+    use nuclear_router::{CaseSensitivity, EncodedSlash, OwnedCaptures, PercentDecoding};
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/files/my doc", 1)
+        .insert("/files/:name", 2)
+        .nest("/v1", |v| {
+            v.insert("/a b", 3);
+        });
+
+    {
+        let ret = router.find("/files/my%20doc").unwrap();
+        assert_eq!(*ret.0, 2);
+        assert_eq!(ret.1.get("name"), Some("my%20doc"));
+        assert_eq!(ret.1.get_decoded("name").unwrap().unwrap(), "my%20doc");
+    }
+
+    router.set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Decode));
+    assert_eq!(*router.find("/files/my%20doc").unwrap().0, 1);
+    assert_eq!(*router.find("/files/my doc").unwrap().0, 1);
+    assert_eq!(*router.find("/v1/a%20b").unwrap().0, 3);
+    {
+        let ret = router.find("/files/a%2Fb%25").unwrap();
+        assert_eq!(*ret.0, 2);
+        assert_eq!(ret.1.get("name"), Some("a%2Fb%25"));
+        assert_eq!(ret.1.get_decoded("name").unwrap().unwrap(), "a/b%");
+        assert_eq!(ret.1.get_decoded("other"), None);
+
+        let caps = OwnedCaptures::new(&ret.1);
+        assert_eq!(caps.get_decoded("name").unwrap().unwrap(), "a/b%");
+    }
+    {
+        let ret = router.find("/files/%E4%BD%A0%zz%").unwrap();
+        assert_eq!(ret.1.get_decoded("name").unwrap().unwrap(), "你%zz%");
+    }
+    {
+        let ret = router.find("/files/%FF").unwrap();
+        assert!(ret.1.get_decoded("name").unwrap().is_err());
+    }
+
+    router.set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Keep));
+    {
+        let ret = router.find("/files/a%2fb%20c").unwrap();
+        assert_eq!(ret.1.get_decoded("name").unwrap().unwrap(), "a%2fb c");
+    }
+
+    router.set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Reject));
+    assert!(router.find("/files/a%2Fb").is_none());
+    assert_eq!(*router.find("/files/a%20b").unwrap().0, 2);
+
+    // constraints are checked against the decoded value
+    router.insert("/f/:name<[a-z ]+>", 4).insert("/n/:id:u8", 5);
+    assert_eq!(*router.find("/f/a%20b").unwrap().0, 4);
+    assert_eq!(*router.find("/n/%31%32").unwrap().0, 5);
+    assert!(router.find("/f/a%2Bb").is_none());
+    assert!(router.find("/f/a%FFb").is_none());
+    router.set_percent_decoding(PercentDecoding::Disabled);
+    assert!(router.find("/f/a%20b").is_none());
+    router.set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Reject));
+
+    router.set_case_sensitivity(CaseSensitivity::AsciiInsensitive);
+    assert_eq!(
+        router.find("/FILES/My%20Doc").unwrap().1.canonical_path(),
+        Some("/files/my%20doc")
+    );
+}
+
+#[test]
+fn router_path_normalization() {
+    use nuclear_router::{EncodedSlash, PercentDecoding, TrailingSlash};
+
+    fn normalized(router: &Router<usize>, path: &str) -> Option<String> {
+        router.normalize_path(path).map(|p| p.into_owned())
+    }
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/", 0)
+        .insert("/user/:name/profile", 1)
+        .insert("/static/*filepath", 2)
+        .insert("/dir/", 3);
+
+    assert!(router.find("//user///asd/./post/../profile").is_none());
+    {
+        let ret = router.find("/static/a/../../etc/passwd").unwrap();
+        assert_eq!(ret.1.get("filepath"), Some("/a/../../etc/passwd"));
+    }
+
+    router.set_path_normalization(true);
+
+    {
+        let ret = router.find("//user///asd/./post/../profile").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(ret.1.get("name"), Some("asd"));
+        assert_eq!(ret.1.redirect(), Some("/user/asd/profile"));
+    }
+    assert_eq!(
+        normalized(&router, "//user///asd/./post/../profile").as_deref(),
+        Some("/user/asd/profile")
+    );
+    {
+        let ret = router.find("/user/asd/profile").unwrap();
+        assert_eq!(*ret.0, 1);
+        assert_eq!(ret.1.get("name"), Some("asd"));
+        assert_eq!(ret.1.redirect(), None);
+    }
+    assert!(router.find("/static/a/../../etc/passwd").is_none());
+    assert_eq!(
+        normalized(&router, "/static/a/../../etc/passwd").as_deref(),
+        Some("/etc/passwd")
+    );
+    assert!(router.find("/etc/passwd").is_none());
+    assert_eq!(normalized(&router, "/.."), None);
+    assert!(router.find("/..").is_none());
+
+    // the wildcard would capture segments which are apart in the path
+    assert!(router.find("/static/a/./b/../c").is_none());
+    assert_eq!(
+        normalized(&router, "/static/a/./b/../c").as_deref(),
+        Some("/static/a/c")
+    );
+    {
+        let ret = router.find("/static/a/c").unwrap();
+        assert_eq!(*ret.0, 2);
+        assert_eq!(ret.1.get("filepath"), Some("/a/c"));
+    }
+    {
+        let ret = router.find("/x/..//static/a/c").unwrap();
+        assert_eq!(*ret.0, 2);
+        assert_eq!(ret.1.get("filepath"), Some("/a/c"));
+        assert_eq!(ret.1.redirect(), Some("/static/a/c"));
+    }
+    assert_eq!(*router.find("/dir/.").unwrap().0, 3);
+    assert_eq!(router.find("//").unwrap().1.redirect(), Some("/"));
+    assert_eq!(
+        normalized(&router, "/static/a/..").as_deref(),
+        Some("/static/")
+    );
+    assert_eq!(normalized(&router, "/dir/.").as_deref(), Some("/dir/"));
+    assert_eq!(normalized(&router, "//").as_deref(), Some("/"));
+    assert_eq!(normalized(&router, "/dir/x/..").as_deref(), Some("/dir/"));
+    assert_eq!(normalized(&router, "/dir/x/../.").as_deref(), Some("/dir/"));
+    assert_eq!(normalized(&router, "/dir//x/..").as_deref(), Some("/dir/"));
+    assert_eq!(
+        normalized(&router, "/user/asd").as_deref(),
+        Some("/user/asd")
+    );
+
+    router.set_trailing_slash(TrailingSlash::Redirect);
+    {
+        let path = normalized(&router, "/user/asd//profile/").unwrap();
+        assert_eq!(path, "/user/asd/profile/");
+        assert_eq!(
+            router.find(&path).unwrap().1.redirect(),
+            Some("/user/asd/profile")
+        );
+        let ret = router.find("/user/asd//profile/").unwrap();
+        assert_eq!(ret.1.get("name"), Some("asd"));
+        assert_eq!(ret.1.redirect(), Some("/user/asd/profile"));
+    }
+
+    // encoded dots are resolved whatever the decoding mode
+    assert!(router.find("/static/%2e%2e/%2e%2e/etc/passwd").is_none());
+    assert_eq!(
+        normalized(&router, "/static/%2e%2e/%2e%2e/etc/passwd"),
+        None
+    );
+    assert!(router.find("/static/%2e%2e/x").is_none());
+    assert_eq!(normalized(&router, "/static/.%2E/x").as_deref(), Some("/x"));
+    assert_eq!(
+        normalized(&router, "/static/%2e/x").as_deref(),
+        Some("/static/x")
+    );
+    assert_eq!(
+        normalized(&router, "/static/%2e%2e%2e/x").as_deref(),
+        Some("/static/%2e%2e%2e/x")
+    );
+    router.set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Reject));
+    assert!(router.find("/static/%2e%2e/x").is_none());
+    assert_eq!(
+        normalized(&router, "/static/a/%2E%2e/x").as_deref(),
+        Some("/static/x")
+    );
+}
+
+#[test]
+fn router_routes() {
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/", 0)
+        .insert_named("posts", "/posts/:page?", 1)
+        .nest("/api/:version<v[12]>", |v| {
+            v.insert("/info(/:lang)", 2).nest("/u/:id:u64", |u| {
+                u.insert("/files/*path", 3);
+            });
+        })
+        .insert("/user/:name.:ext", 4);
+
+    let routes: Vec<(String, usize)> = router.routes().map(|(p, &t)| (p, t)).collect();
+    assert_eq!(
+        routes,
+        [
+            ("/".to_owned(), 0),
+            ("/posts/:page?".to_owned(), 1),
+            ("/api/:version<v[12]>/info(/:lang)".to_owned(), 2),
+            ("/api/:version<v[12]>/u/:id:u64/files/*path".to_owned(), 3),
+            ("/user/:name.:ext".to_owned(), 4),
+        ]
+    );
+
+    router.remove("/posts");
+    router.remove("/posts/:page?");
+    router.remove("/api/:version<v[12]>/info");
+    let routes: Vec<String> = router.routes().map(|(p, _)| p).collect();
+    assert_eq!(
+        routes,
+        [
+            "/",
+            "/api/:version<v[12]>/info(/:lang)",
+            "/api/:version<v[12]>/u/:id:u64/files/*path",
+            "/user/:name.:ext",
+        ]
+    );
+
+    router.clear();
+    assert_eq!(router.routes().count(), 0);
+}
+
+#[test]
+fn router_find_all() {
+    use nuclear_router::Rejection;
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/u/:uid/p/:pid", 1)
+        .insert("/u/me/p/:pid", 2)
+        .insert(r"/u/:uid<\d+>/p/:pid", 3)
+        .insert("/u/*path", 4)
+        .insert("/u/:uid/p", 5)
+        .insert("/u/:name.:ext/p/:pid", 6)
+        .nest("/n/:uid", |n| {
+            n.insert("/p/:pid", 7).insert("/p/:pid/info", 8);
+        });
+
+    {
+        let all = router.find_all("/u/me/p/1");
+        let ids: Vec<usize> = all.iter().map(|(&t, _)| t).collect();
+        assert_eq!(ids, [2, 1, 4]);
+        assert_eq!(&*all[0].1, &[("pid", "1")]);
+        assert_eq!(&*all[1].1, &[("uid", "me"), ("pid", "1")]);
+        assert_eq!(&*all[2].1, &[("path", "/me/p/1")]);
+        assert_eq!(*router.find("/u/me/p/1").unwrap().0, ids[0]);
+    }
+    {
+        let all = router.find_all("/u/42/p/1");
+        let ids: Vec<usize> = all.iter().map(|(&t, _)| t).collect();
+        assert_eq!(ids, [3, 1, 4]);
+    }
+    {
+        let all = router.find_all("/n/me/p/1");
+        assert_eq!(all.len(), 1);
+        assert_eq!(*all[0].0, 7);
+        assert_eq!(&*all[0].1, &[("uid", "me"), ("pid", "1")]);
+    }
+    assert!(router.find_all("/x").is_empty());
+
+    let explain = router.explain("/n/a/p/1");
+    let reasons: Vec<Rejection> = explain.iter().map(|&(_, r)| r).collect();
+    assert_eq!(
+        reasons,
+        [
+            Rejection::Static { index: 0 },
+            Rejection::Static { index: 0 },
+            Rejection::Static { index: 0 },
+            Rejection::Static { index: 0 },
+            Rejection::SegmentCount {
+                expected: 3,
+                actual: 4
+            },
+            Rejection::Static { index: 0 },
+            Rejection::SegmentCount {
+                expected: 5,
+                actual: 4
+            },
+        ]
+    );
+    assert_eq!(explain[6].0, "/n/:uid/p/:pid/info");
+    assert_eq!(
+        router.explain("/n/a/p/1/x").last().unwrap(),
+        &(
+            "/n/:uid/p/:pid/info".to_owned(),
+            Rejection::Static { index: 4 }
+        )
+    );
+    assert_eq!(
+        router.explain("/u/a/p/1")[..2],
+        [
+            ("/u/me/p/:pid".to_owned(), Rejection::Static { index: 1 }),
+            (
+                r"/u/:uid<\d+>/p/:pid".to_owned(),
+                Rejection::Constraint { index: 1 }
+            ),
+        ]
+    );
+    assert!(router.explain("/u/x/p/1").contains(&(
+        "/u/:name.:ext/p/:pid".to_owned(),
+        Rejection::Affix { index: 1 }
+    )));
+    assert!(router.explain("/u").contains(&(
+        "/u/*path".to_owned(),
+        Rejection::Wildcard { min: 2, actual: 1 }
+    )));
+    assert!(router.explain("/x/y").contains(&(
+        "/n/:uid/p/:pid".to_owned(),
+        Rejection::Wildcard { min: 3, actual: 2 }
+    )));
+    assert_eq!(
+        Rejection::Static { index: 1 }.to_string(),
+        "static segment 1 does not match"
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn router_deserialize() {
+    use nuclear_router::{EncodedSlash, OwnedCaptures, PercentDecoding};
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        Post,
+        Page,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Params<'a> {
+        kind: Kind,
+        id: u64,
+        draft: bool,
+        slug: &'a str,
+        lang: Option<String>,
+    }
+
+    let mut router: Router<usize> = Router::new();
+    router
+        .insert("/:kind/:id/:draft/:slug", 1)
+        .insert("/item/:id", 2);
+
+    let (_, caps) = router.find("/post/42/true/hello").unwrap();
+    let params: Params<'_> = caps.deserialize().unwrap();
+    assert_eq!(
+        params,
+        Params {
+            kind: Kind::Post,
+            id: 42,
+            draft: true,
+            slug: "hello",
+            lang: None,
+        }
+    );
+
+    let (kind, id, draft, slug): (Kind, u32, bool, String) = caps.deserialize().unwrap();
+    assert_eq!((kind, id, draft, &*slug), (Kind::Post, 42, true, "hello"));
+
+    let owned = OwnedCaptures::new(&caps);
+    let params: Params<'_> = owned.deserialize().unwrap();
+    assert_eq!((params.kind, params.id), (Kind::Post, 42));
+
+    let (_, caps) = router.find("/page/x/true/hello").unwrap();
+    let err = caps.deserialize::<Params<'_>>().unwrap_err();
+    assert_eq!(err.param(), Some("id"));
+    assert_eq!(
+        err.to_string(),
+        "invalid parameter `id`: invalid digit found in string"
+    );
+
+    let (_, caps) = router.find("/blog/1/true/hello").unwrap();
+    assert_eq!(
+        caps.deserialize::<Params<'_>>().unwrap_err().param(),
+        Some("kind")
+    );
+
+    let (_, caps) = router.find("/item/7").unwrap();
+    assert_eq!(caps.deserialize::<u8>().unwrap(), 7);
+    assert_eq!(
+        caps.deserialize::<Params<'_>>().unwrap_err().param(),
+        Some("kind")
+    );
+    assert!(caps.deserialize::<(u8, u8)>().is_err());
+
+    // the values are decoded like `get_decoded` does
+    let mut router: Router<usize> = Router::new();
+    router
+        .set_percent_decoding(PercentDecoding::Enabled(EncodedSlash::Decode))
+        .insert("/files/:name", 1);
+    let (_, caps) = router.find("/files/my%20doc").unwrap();
+    assert_eq!(caps.deserialize::<String>().unwrap(), "my doc");
+    let owned = OwnedCaptures::new(&caps);
+    assert_eq!(owned.deserialize::<(String,)>().unwrap().0, "my doc");
+
+    let (_, caps) = router.find("/files/%FF").unwrap();
+    let err = caps.deserialize::<String>().unwrap_err();
+    assert_eq!(err.param(), Some("name"));
+}
+
+#[test]
+fn query_params() {
+    use nuclear_router::{OwnedQueryParams, QueryParams};
+
+    let params = QueryParams::new("a=1&b=x+y%21&&a=2&flag&c=%E4%BD%A0%FF&a%20b=3");
+    assert_eq!(params.len(), 6);
+    assert_eq!(params.get("a"), Some("1"));
+    assert_eq!(params.get_all("a").collect::<Vec<_>>(), ["1", "2"]);
+    assert_eq!(params.get("b"), Some("x y!"));
+    assert_eq!(params.get("flag"), Some(""));
+    assert_eq!(params.get("c"), Some("你\u{FFFD}"));
+    assert_eq!(params.get("a b"), Some("3"));
+    assert_eq!(params.get("d"), None);
+    assert_eq!(params.parse::<u8>("a"), Some(Ok(1)));
+    assert!(params.parse::<u8>("b").unwrap().is_err());
+
+    let owned = OwnedQueryParams::new(&params);
+    assert_eq!(owned.get_all("a").collect::<Vec<_>>(), ["1", "2"]);
+    assert_eq!(owned.iter().count(), 6);
+    let pairs: Vec<(String, String)> = owned.into_iter().collect();
+    assert_eq!(pairs[1], ("b".to_owned(), "x y!".to_owned()));
+
+    assert!(QueryParams::new("").is_empty());
+    assert!(QueryParams::new("&&").is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn query_params_deserialize() {
+    use nuclear_router::{OwnedQueryParams, QueryParams};
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Search<'a> {
+        q: String,
+        page: Option<u32>,
+        #[serde(borrow)]
+        tag: Vec<&'a str>,
+        #[serde(default)]
+        exact: bool,
+    }
+
+    let params = QueryParams::new("q=hello+world&tag=a&page=2&tag=b");
+    let search: Search<'_> = params.deserialize().unwrap();
+    assert_eq!(
+        search,
+        Search {
+            q: "hello world".into(),
+            page: Some(2),
+            tag: vec!["a", "b"],
+            exact: false,
+        }
+    );
+
+    let owned = OwnedQueryParams::new(&QueryParams::new("q=x&tag=a&exact=true&q=y"));
+    let search: Search<'_> = owned.deserialize().unwrap();
+    assert_eq!(
+        (&*search.q, &*search.tag, search.exact),
+        ("x", &["a"][..], true)
+    );
+
+    let params = QueryParams::new("q=x&page=two");
+    let err = params.deserialize::<Search<'_>>().unwrap_err();
+    assert_eq!(err.param(), Some("page"));
+
+    let params = QueryParams::new("page=1");
+    assert_eq!(
+        params.deserialize::<Search<'_>>().unwrap_err().param(),
+        Some("q")
+    );
+}